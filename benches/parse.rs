@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lib1337patch::{F1337Patch, HexPatch};
+use std::hint::black_box;
+use std::io::Cursor;
+
+/// This builds an in-memory patch file of ``lines`` patch lines.
+fn patch_file(lines: u64) -> String {
+    let mut content = String::from(">test.exe\n");
+
+    for address in 0..lines {
+        content.push_str(&format!("{}\n", HexPatch::new(address * 2, 0x13, 0x37)));
+    }
+    content
+}
+
+fn bench_from_bufreader(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_bufreader");
+
+    for lines in [1_000, 10_000, 100_000] {
+        let content = patch_file(lines);
+
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(lines), &content, |b, content| {
+            b.iter(|| F1337Patch::from_bufreader(&mut Cursor::new(black_box(content.as_bytes()))).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_from_bufreader);
+criterion_main!(benches);
@@ -1,16 +1,55 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
-use std::io::{self, BufRead, Seek};
+use std::io::{self, BufRead, Read, Seek, Write};
 
 pub trait SeekableBufRead: BufRead + Seek {}
 impl<R: BufRead + Seek> SeekableBufRead for R {}
 
+/// The x86 ``NOP`` opcode, used by [HexPatch::nop] and [F1337Patch::nop_range].
+pub const X86_NOP: u8 = 0x90;
+
+/// The ``(old, new)`` byte transitions turning an x86 conditional jump into an unconditional one, used by [F1337Patch::count_jump_patches].
+/// 
+/// - ``0x70..=0x7F -> 0xEB``: a short ``Jcc rel8`` becomes a short ``JMP rel8``.
+/// - ``0x80..=0x8F -> 0xE9``: the second opcode byte of a near ``Jcc rel32`` (``0F 8x``) becomes a near ``JMP rel32``,
+///   the ``0F`` byte being usually replaced by a [X86_NOP].
+pub const JUMP_PATCH_TRANSITIONS: [(u8, u8); 32] = jump_patch_transitions();
+
+/// This builds [JUMP_PATCH_TRANSITIONS].
+const fn jump_patch_transitions() -> [(u8, u8); 32] {
+    let mut transitions = [(0, 0); 32];
+    let mut i = 0;
+
+    while i < 16 {
+        transitions[i] = (0x70 + i as u8, 0xEB);
+        transitions[16 + i] = (0x80 + i as u8, 0xE9);
+        i += 1;
+    }
+    transitions
+}
+
+/// The latest patch format version supported, see [F1337Patch::format_version].
+/// 
+/// - Version 1: single-byte patches.
+pub const LATEST_FORMAT_VERSION: u32 = 1;
+
+/// Magic bytes starting the compact binary form written by [F1337Patch::to_compact_binary].
+const COMPACT_MAGIC: &[u8; 4] = b"1337";
+
+/// Magic bytes starting the journal written by [F1337Patch::apply_to_with_journal].
+const JOURNAL_MAGIC: &[u8; 8] = b"1337JRNL";
+
+/// Byte order mark stripped from the lines when [ParseOptions::strip_bom] is enabled.
+const BOM: char = '\u{FEFF}';
+
 /// Enum representing the different errors that can occur when reading a patch file.
 /// 
 /// See [Variants](#variants) for variants and their meaning.
 pub enum PatchFileError {
     /// When the radix or any other conversion fails.
     /// 
-    /// Occurs if the values are not in hex.
+    /// Occurs if the values are not in hex, or if the address field of a patch line is longer than 16 digits,
+    /// which is reported like a number too large to fit in a [u64].
     /// 
     /// This encapsulates [std::num::ParseIntError].
     ConvertionError(std::num::ParseIntError),
@@ -26,6 +65,71 @@ pub enum PatchFileError {
     /// Occurs if the file is not in the right format.<br/>
     /// Can bee too long, too short values, lines not in the right format, and so on.
     WrongFormat,
+    /// When the header line is not in the right format.
+    /// 
+    /// Occurs if the header does not start with ``>`` or does not name a target file.<br/>
+    /// The [String] describes what is wrong with the header.
+    InvalidHeader(String),
+    /// When the target does not contain the expected old value.
+    /// 
+    /// Occurs if the byte at ``address`` in the target is not the [old value](HexPatch::old) of the patch.<br/>
+    /// If this happens, the target is probably not the file the patch was made for, or it is already patched.
+    OldValueMismatch {
+        /// Address of the mismatching byte.
+        address: u64,
+        /// Old value declared by the patch.
+        expected: u8,
+        /// Value found in the target.
+        found: u8,
+    },
+    /// When an address is past the end of the target.
+    /// 
+    /// Occurs if the target is too short to contain the patched byte at the given address.
+    OutOfBounds(u64),
+    /// When two patches write different values at the same address.
+    /// 
+    /// Occurs if the result of the patch would depend on the order the patches are applied.<br/>
+    /// Contains the address of the conflicting patches.
+    ConflictingPatch(u64),
+    /// When an address is out of the representable range.
+    /// 
+    /// Occurs if computing an address (or the end of the byte at an address) overflows a [u64].<br/>
+    /// Contains the address that could not be computed from.
+    AddressOverflow(u64),
+    /// When the file contains more patches than allowed.
+    /// 
+    /// Occurs if the number of patches exceeds [ParseOptions::max_patches].<br/>
+    /// Contains the maximum number of patches allowed.
+    TooManyPatches(usize),
+    /// When several patches target the same address.
+    /// 
+    /// Occurs if [F1337Patch::require_unique_addresses] finds a repeated address, whatever the patch values.<br/>
+    /// Contains the first repeated address.
+    DuplicateAddress(u64),
+    /// When a patch format version is not supported.
+    /// 
+    /// Occurs if a version is unknown, i.e. 0 or greater than [LATEST_FORMAT_VERSION], or older than the current version of the patch set.<br/>
+    /// Contains the unsupported version.
+    UnsupportedVersion(u32),
+    /// When a patch targets an address outside the permitted address window.
+    /// 
+    /// Occurs if [F1337Patch::reject_outside_window] finds a patch outside the window.<br/>
+    /// Contains the address of the first such patch.
+    OutsideWindow(u64),
+    /// When a journal file is not in the right format.
+    /// 
+    /// Occurs if a journal does not start with the journal magic bytes, or if it is truncated in the middle of an entry.<br/>
+    /// See [F1337Patch::apply_to_with_journal] for the journal format.
+    InvalidJournal,
+    /// When the SHA-256 hash of the target is not the expected one.
+    /// 
+    /// Occurs if [F1337Patch::apply_if_hash] is given a target which is not the expected binary version.
+    HashMismatch {
+        /// Expected SHA-256 hash of the target.
+        expected: [u8; 32],
+        /// SHA-256 hash of the target.
+        found: [u8; 32],
+    },
 }
 
 /// Implement [std::fmt::Debug] trait for [PatchFileError]
@@ -36,6 +140,22 @@ impl std::fmt::Debug for PatchFileError {
             PatchFileError::ConvertionError(e) => write!(f, "ConvertionError: {}", e),
             PatchFileError::ReadError(e) => write!(f, "ReadError: {}", e),
             PatchFileError::WrongFormat => write!(f, "Error : WrongFormat: The file/buffer data structure is invalid!"),
+            PatchFileError::InvalidHeader(reason) => write!(f, "InvalidHeader: {}", reason),
+            PatchFileError::OldValueMismatch { address, expected, found } => write!(f, "OldValueMismatch: expected {:02X} at {:#X}, found {:02X}", expected, address, found),
+            PatchFileError::OutOfBounds(address) => write!(f, "OutOfBounds: {:#X} is past the end of the target", address),
+            PatchFileError::ConflictingPatch(address) => write!(f, "ConflictingPatch: different new values at {:#X}", address),
+            PatchFileError::AddressOverflow(address) => write!(f, "AddressOverflow: {:#X} is out of the addressable range", address),
+            PatchFileError::TooManyPatches(max) => write!(f, "TooManyPatches: more than {} patches", max),
+            PatchFileError::DuplicateAddress(address) => write!(f, "DuplicateAddress: {:#X} is patched more than once", address),
+            PatchFileError::UnsupportedVersion(version) => write!(f, "UnsupportedVersion: format version {} is not supported", version),
+            PatchFileError::OutsideWindow(address) => write!(f, "OutsideWindow: {:#X} is outside the permitted address window", address),
+            PatchFileError::InvalidJournal => write!(f, "InvalidJournal: the journal is not in the right format or is truncated"),
+            PatchFileError::HashMismatch { expected, found } => {
+                write!(f, "HashMismatch: expected ")?;
+                expected.iter().try_for_each(|byte| write!(f, "{:02x}", byte))?;
+                write!(f, ", found ")?;
+                found.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            },
         }
     }
 }
@@ -57,9 +177,69 @@ impl PartialEq for PatchFileError {
                     _ => false,
                 }
             },
-            PatchFileError::WrongFormat => {
+            PatchFileError::WrongFormat => matches!(other, PatchFileError::WrongFormat),
+            PatchFileError::InvalidJournal => matches!(other, PatchFileError::InvalidJournal),
+            PatchFileError::InvalidHeader(reason_self) => {
+                match other {
+                    PatchFileError::InvalidHeader(reason_other) => reason_self == reason_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::OldValueMismatch { address, expected, found } => {
+                match other {
+                    PatchFileError::OldValueMismatch { address: address_other, expected: expected_other, found: found_other } => {
+                        address == address_other && expected == expected_other && found == found_other
+                    },
+                    _ => false,
+                }
+            },
+            PatchFileError::OutOfBounds(address_self) => {
                 match other {
-                    PatchFileError::WrongFormat => true,
+                    PatchFileError::OutOfBounds(address_other) => address_self == address_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::ConflictingPatch(address_self) => {
+                match other {
+                    PatchFileError::ConflictingPatch(address_other) => address_self == address_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::AddressOverflow(address_self) => {
+                match other {
+                    PatchFileError::AddressOverflow(address_other) => address_self == address_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::TooManyPatches(max_self) => {
+                match other {
+                    PatchFileError::TooManyPatches(max_other) => max_self == max_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::DuplicateAddress(address_self) => {
+                match other {
+                    PatchFileError::DuplicateAddress(address_other) => address_self == address_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::UnsupportedVersion(version_self) => {
+                match other {
+                    PatchFileError::UnsupportedVersion(version_other) => version_self == version_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::OutsideWindow(address_self) => {
+                match other {
+                    PatchFileError::OutsideWindow(address_other) => address_self == address_other,
+                    _ => false,
+                }
+            },
+            PatchFileError::HashMismatch { expected: expected_self, found: found_self } => {
+                match other {
+                    PatchFileError::HashMismatch { expected: expected_other, found: found_other } => {
+                        expected_self == expected_other && found_self == found_other
+                    },
                     _ => false,
                 }
             },
@@ -93,7 +273,7 @@ impl From<std::io::Error> for PatchFileError {
 /// ```text
 /// 0000000000AF0200:13->37
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct HexPatch {
     /// Target address of the patch.
     pub target_address: u64,
@@ -127,6 +307,32 @@ impl HexPatch {
             new,
         }
     }
+
+    /// This creates a [HexPatch] that replaces the byte at ``address`` with an x86 ``NOP`` ([X86_NOP]).
+    /// 
+    /// # Arguments
+    /// - ``address`` - The target address of the patch.
+    /// - ``old`` - The old value of the patch, i.e. the byte being NOP-ed out.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{HexPatch, X86_NOP};
+    /// 
+    /// let patch = HexPatch::nop(0x0000000000AF0200, 0x74);
+    /// 
+    /// assert_eq!(patch.new, X86_NOP);
+    /// ```
+    pub fn nop(address: u64, old: u8) -> HexPatch {
+        HexPatch::new(address, old, X86_NOP)
+    }
+
+    /// This function writes the patch as a patch line with hex digits in the given case.
+    fn to_string_case(self, case: HexCase) -> String {
+        match case {
+            HexCase::Upper => self.to_string(),
+            HexCase::Lower => format!("{:016x}:{:02x}->{:02x}", self.target_address, self.old, self.new),
+        }
+    }
 }
 
 /// Implement [PartialEq] for [HexPatch]
@@ -139,321 +345,7175 @@ impl PartialEq for HexPatch {
     }
 }
 
-/// This is used to create representation of the patch file.
-/// 
-/// Path files are in the following format:<br/>
-/// The first line start with ``>`` and followed by the target file name.
-/// 
-/// The rest of the lines are patches in the following format:<br/>
-/// [``TargetAddress``](HexPatch::target_address):[``Old``](HexPatch::old)->[``New``](HexPatch::new) all in HEX in a TXT file.
-/// 
-/// Target address is always 16 hex digits long, old value and new value are always 2 hex digits long.
-/// 
-/// # Example
-/// ```text
-/// >test.exe
-/// 0000000000AF0200:13->37
-/// 0000000000AF0206:37->37
-/// ```
+/// This is used to create representation of a patch spanning several contiguous bytes.
 /// 
-/// Patches are stored in a vector of [HexPatch].
-#[derive(Debug)]
-pub struct F1337Patch {
-    /// Target file name. Extracted from the first line of the patch file.
-    pub target_filename: String,
-    /// Vector of patches. Builded from extracted data from the rest of the lines of the patch file.
-    pub patches: Vec<HexPatch>,
+/// The n-th byte of [``Old``](RangePatch::old) and [``New``](RangePatch::new) is the old and new value at [``TargetAddress``](RangePatch::target_address) + n.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangePatch {
+    /// Target address of the first byte of the patch.
+    pub target_address: u64,
+    /// Old values of the patch.
+    pub old: Vec<u8>,
+    /// New values of the patch. Always as long as [``Old``](RangePatch::old).
+    pub new: Vec<u8>,
 }
 
-impl F1337Patch {
-    /// This creates an new empty [F1337Patch].
+/// Implementation of [RangePatch]
+impl RangePatch {
+    /// This is the constructor of [RangePatch]
     /// 
-    /// # Example
-    /// ```rust
-    /// use lib1337patch::F1337Patch;
-    /// use lib1337patch::HexPatch;
+    /// # Arguments
+    /// - ``address`` - The target address of the first byte of the patch.
+    /// - ``old`` - The old values of the patch.
+    /// - ``new`` - The new values of the patch.
     /// 
-    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
-    /// let patch = HexPatch::new(0x0000000000AF0200, 0x13, 0x37);
+    /// # Panics
+    /// Panics if ``old`` and ``new`` do not have the same length.
     /// 
-    /// f1337patch.add_patch(patch);
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::RangePatch;
     /// 
-    /// println!("File name : {}", f1337patch.target_filename);
-    /// println!("Patches : {:?}", f1337patch.patches);
+    /// let patch = RangePatch::new(0x0000000000AF0200, vec![0x74, 0x05], vec![0x90, 0x90]);
     /// ```
-    pub fn new(target_filename: String) -> Self {
-        F1337Patch {
-            target_filename,
-            patches: Vec::new(),
+    pub fn new(address: u64, old: Vec<u8>, new: Vec<u8>) -> RangePatch {
+        assert_eq!(old.len(), new.len(), "old and new values of a range patch must have the same length");
+
+        RangePatch {
+            target_address: address,
+            old,
+            new,
         }
     }
 
-    /// This adds a patch to the [F1337Patch].
+    /// This checks that ``target`` contains the [old values](RangePatch::old) of the patch.
     /// 
-    /// To create a [HexPatch], use [HexPatch::new].
+    /// Exactly the length of the range is read: a target ending in the middle of the range is an error, not a partial match.
     /// 
     /// # Arguments
-    /// - ``patch``: A [HexPatch]. Can be created with [HexPatch::new].
+    /// - ``target``: A mutable reference to anything that implements [Read] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of ``true`` if the old values match, ``false`` otherwise, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::OutOfBounds] if the range runs past the end of ``target``. Contains the address of the range.
+    /// - [PatchFileError::ReadError] if the target can't be read or seeked. Contains [std::io::Error].
     /// 
     /// # Example
     /// ```rust
-    /// use lib1337patch::F1337Patch;
-    /// use lib1337patch::HexPatch;
+    /// use lib1337patch::RangePatch;
+    /// use std::io::Cursor;
     /// 
-    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let patch = RangePatch::new(0x01, vec![0x74, 0x05], vec![0x90, 0x90]);
     /// 
-    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
-    /// println!("File name : {}", f1337patch.target_filename);
+    /// assert!(patch.verify(&mut Cursor::new(vec![0x00, 0x74, 0x05])).unwrap());
     /// ```
-    pub fn add_patch(&mut self, patch: HexPatch) {
-        self.patches.push(patch);
+    pub fn verify<R: Read + Seek>(&self, target: &mut R) -> Result<bool, PatchFileError> {
+        let mut current = vec![0u8; self.old.len()];
+
+        target.seek(io::SeekFrom::Start(self.target_address))?;
+        match target.read_exact(&mut current) {
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Err(PatchFileError::OutOfBounds(self.target_address)),
+            Err(error) => Err(error.into()),
+            Ok(()) => Ok(current == self.old),
+        }
     }
 
-    /// This creates a new [F1337Patch] from a [File].
-    /// 
-    /// It takes a mutable reference to a [File] and returns a [Result] of [F1337Patch] or [PatchFileError].
-    /// 
-    /// This function is a wrapper for [F1337Patch::from_bufreader].
-    /// 
-    /// # Arguments
-    /// - ``patchfile``: A mutable reference to a [File].
+    /// This counts the bytes actually changed by the patch, i.e. the positions of the range where the old and new values differ.
     /// 
     /// # Returns
-    /// - Result of [F1337Patch] or [PatchFileError].
-    /// 
-    /// # Errors
-    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
-    /// - [PatchFileError::ReadError] if the file can't be read. Contains [std::io::Error].
-    /// - [PatchFileError::WrongFormat] if the file is not in the right format.
+    /// - The number of changed bytes, at most the length of the range.
     /// 
     /// # Example
-    /// ```rust,no_run
-    /// use lib1337patch::F1337Patch;
-    /// use std::fs::File;
+    /// ```rust
+    /// use lib1337patch::RangePatch;
     /// 
-    /// let mut patchfile = File::open("test.txt").unwrap();
+    /// let patch = RangePatch::new(0x01, vec![0x74, 0x05, 0x90, 0x90], vec![0x90, 0x90, 0x90, 0x90]);
     /// 
-    /// let patch = F1337Patch::from_patchfile(&mut patchfile).unwrap();
+    /// assert_eq!(patch.changed_byte_count(), 2);
     /// ```
-    pub fn from_patchfile(patchfile: &File) -> Result<F1337Patch, PatchFileError> {
-        Self::from_bufreader(&mut io::BufReader::new(patchfile))
+    pub fn changed_byte_count(&self) -> usize {
+        self.old.iter().zip(&self.new).filter(|(old, new)| old != new).count()
     }
+}
 
-    /// This creates a new [F1337Patch] from a [std::io::BufReader].
-    /// 
-    /// It takes a mutable reference to a [File] and returns a [Result] of [F1337Patch] or [PatchFileError].
+/// This is used to create representation of a patch filling ``count`` bytes with the same value.
+/// 
+/// Some tools express "fill N bytes with X starting at address" with a line such as ``0000000000AF0200:*->90*16``:
+/// the old value is either 2 hex digits or ``*`` for any value, and the decimal count follows the new value.
+/// 
+/// A fill patch is kept compact, and expanded into single-byte patches with [FillPatch::expand].
+/// Fill lines of a patch file are expanded when it is read, see [F1337Patch].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FillPatch {
+    /// Target address of the first filled byte.
+    pub address: u64,
+    /// Old value of every filled byte, or [None] for any value.
+    pub old: Option<u8>,
+    /// New value of every filled byte.
+    pub new: u8,
+    /// Number of filled bytes.
+    pub count: usize,
+}
+
+/// Implementation of [FillPatch]
+impl FillPatch {
+    /// This is the constructor of [FillPatch]
     /// 
     /// # Arguments
-    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
-    /// 
-    /// # Returns
-    /// - Result of [F1337Patch] or [PatchFileError].
-    /// 
-    /// # Errors
-    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
-    /// - [PatchFileError::ReadError] if the file can't be read. Contains [std::io::Error].
-    /// - [PatchFileError::WrongFormat] if the file is not in the right format.
+    /// - ``address`` - The target address of the first filled byte.
+    /// - ``old`` - The old value of every filled byte, or [None] for any value.
+    /// - ``new`` - The new value of every filled byte.
+    /// - ``count`` - The number of filled bytes.
     /// 
     /// # Example
-    /// ```rust,no_run
-    /// use lib1337patch::F1337Patch;
-    /// use std::fs::File;
+    /// ```rust
+    /// use lib1337patch::{FillPatch, X86_NOP};
     /// 
-    /// let mut patchfile = File::open("test.txt").unwrap();
-    /// let patch = F1337Patch::from_patchfile(&patchfile).unwrap();
+    /// let patch = FillPatch::new(0x0000000000AF0200, None, X86_NOP, 16);
     /// ```
-    /// 
-    /// # Note
-    /// See [F1337Patch] for more information about the file format.
-    pub fn from_bufreader<R: SeekableBufRead>(bufreader: &mut R) -> Result<F1337Patch, PatchFileError> {
-        let mut f1337patch: F1337Patch;
-        let mut first_line = String::new();
-        
-        bufreader.seek(io::SeekFrom::Start(0)).unwrap();
-        bufreader.read_line(&mut first_line)?;
-        f1337patch = F1337Patch::new(Self::get_filename(first_line)?);
-
-        for result in bufreader.lines() {
-            let line = result?;
-
-            Self::check_patch_line_format(&line)?;
-            f1337patch.patches.push(Self::get_hex_patch_from_line(&line)?);
+    pub fn new(address: u64, old: Option<u8>, new: u8, count: usize) -> FillPatch {
+        FillPatch {
+            address,
+            old,
+            new,
+            count,
         }
-        
-        Ok(f1337patch)
     }
 
-    /// This function checks that patch line is in the right format.
+    /// This parses a fill patch line, such as ``0000000000AF0200:*->90*16``.
     /// 
     /// # Arguments
-    /// - ``line``: A mutable reference to a [String].
+    /// - ``line``: The line to parse, without its line ending.
     /// 
     /// # Returns
-    /// - [Result] of [()] or [PatchFileError].
+    /// - Result of [FillPatch] or [PatchFileError].
     /// 
     /// # Errors
-    /// - [PatchFileError::WrongFormat] if the line is not in the right format.
+    /// - [PatchFileError::WrongFormat] if the line is not a fill patch line, or if its count is 0.
+    /// - [PatchFileError::AddressOverflow] if the last filled byte is out of the addressable range. Contains the address.
     /// 
     /// # Example
     /// ```rust
-    /// use lib1337patch::F1337Patch;
+    /// use lib1337patch::{FillPatch, X86_NOP};
     /// 
-    /// let line = "0000000000AF0200:13->37".to_string();
-    /// F1337Patch::check_patch_line_format(&line).unwrap();
-    /// ```
+    /// let patch = FillPatch::from_line("0000000000AF0200:*->90*16").unwrap();
     /// 
-    /// # Note
-    /// See [F1337Patch] for more information about the file format.
-    pub fn check_patch_line_format(line: &String) -> Result<(), PatchFileError> {
-        // Check if line is 23 characters long.
-        if line.len() != 23 {
-            return Err(PatchFileError::WrongFormat);
-        }
-        // Check the presence of ":" and "->" in the right place.
-        if &line[16..17] != ":" {
-            return Err(PatchFileError::WrongFormat);
-        }
-        if &line[19..21] != "->" {
+    /// assert_eq!(patch, FillPatch::new(0x0000000000AF0200, None, X86_NOP, 16));
+    /// ```
+    pub fn from_line(line: &str) -> Result<FillPatch, PatchFileError> {
+        let (address, values) = line.split_once(':').ok_or(PatchFileError::WrongFormat)?;
+        let (old, values) = values.split_once("->").ok_or(PatchFileError::WrongFormat)?;
+        let (new, count) = values.split_once('*').ok_or(PatchFileError::WrongFormat)?;
+        let is_hex = |digits: &str, len: usize| digits.len() == len && digits.bytes().all(|digit| digit.is_ascii_hexdigit());
+
+        if !is_hex(address, 16) || !(old == "*" || is_hex(old, 2)) || !is_hex(new, 2) {
             return Err(PatchFileError::WrongFormat);
         }
-        // Check if address, old an new values are only in hex digits.
-        if !line[0..16].chars().all(|c| c.is_digit(16)) {
+        if count.is_empty() || !count.bytes().all(|digit| digit.is_ascii_digit()) {
             return Err(PatchFileError::WrongFormat);
         }
-        if !line[17..19].chars().all(|c| c.is_digit(16)) {
+
+        let patch = FillPatch::new(
+            u64::from_str_radix(address, 16)?,
+            match old {
+                "*" => None,
+                old => Some(u8::from_str_radix(old, 16)?),
+            },
+            u8::from_str_radix(new, 16)?,
+            count.parse()?,
+        );
+        if patch.count == 0 {
             return Err(PatchFileError::WrongFormat);
         }
-        if !line[21..23].chars().all(|c| c.is_digit(16)) {
-            return Err(PatchFileError::WrongFormat);
+        if patch.address.checked_add(patch.count as u64 - 1).is_none() {
+            return Err(PatchFileError::AddressOverflow(patch.address));
         }
-        Ok(())
+        Ok(patch)
     }
 
-    /// This function extracts patch from given line.
-    /// 
-    /// # Arguments
-    /// - ``line``: A reference to a [String].
+    /// This expands the fill patch into one [HexPatch] per filled byte.
     /// 
     /// # Returns
-    /// - [Result] of [HexPatch] or [PatchFileError].
-    /// 
-    /// # Errors
-    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - The single-byte patches, in address order, or [None] if the old value is any value,
+    ///   as a [HexPatch] needs one. Use [F1337Patch::resync_old_from] on patches built with a placeholder old value instead.
+    /// - [None] as well if the last filled byte is out of the addressable range, which [FillPatch::from_line] rejects
+    ///   but [FillPatch::new] and the public fields do not.
     /// 
     /// # Example
     /// ```rust
-    /// use lib1337patch::F1337Patch;
+    /// use lib1337patch::{FillPatch, X86_NOP};
     /// 
-    /// let line = "0000000000AF0200:13->37".to_string();
-    /// let patch = F1337Patch::get_hex_patch_from_line(&line).unwrap();
+    /// let patch = FillPatch::new(0x0000000000AF0200, Some(0x00), X86_NOP, 16);
+    /// 
+    /// assert_eq!(patch.expand().unwrap().len(), 16);
     /// ```
-    pub fn get_hex_patch_from_line(line: &String) -> Result<HexPatch, std::num::ParseIntError> {
-        let address = u64::from_str_radix(&line[0..16], 16)?;
-        let old = u8::from_str_radix(&line[17..19], 16)?;
-        let new = u8::from_str_radix(&line[21..23], 16)?;
+    pub fn expand(&self) -> Option<Vec<HexPatch>> {
+        let old = self.old?;
+        if self.count > 0 {
+            self.address.checked_add(self.count as u64 - 1)?;
+        }
 
-        Ok(HexPatch::new(address, old, new))
+        Some((0..self.count as u64).map(|offset| HexPatch::new(self.address + offset, old, self.new)).collect())
     }
+}
 
-    /// This function extract filename from the first line of the patch file.
-    /// The first line start with ">" and followed by the target file name.
-    fn get_filename(first_line: String) -> Result<String, PatchFileError> {
-        if !first_line.starts_with('>') {
-            return Err(PatchFileError::WrongFormat);
+/// Implement [std::fmt::Display] for [FillPatch]
+impl std::fmt::Display for FillPatch {
+    /// This is the implementation of [std::fmt::Display::fmt] for [FillPatch].
+    /// 
+    /// The patch is written in the line form read by [FillPatch::from_line].
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:016X}:", self.address)?;
+        match self.old {
+            Some(old) => write!(f, "{:02X}", old)?,
+            None => write!(f, "*")?,
         }
-        
-        // This returns the filename. Trim the end to remove the \n (and \r\n on windows).
-        Ok(first_line[1..].trim_end().to_string())
+        write!(f, "->{:02X}*{}", self.new, self.count)
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use tempfile::tempfile;
-    use std::io::Write;
-    
-        // TODO : Add some fuzzing for [F1337Patch::new] and [F1337Patch::from_filepatch] to test more cases.
-        // TODO : Add more fuzzing for [F1337Patch::check_patch_line_format] to test more cases.
-    
-    #[test]
-    fn test_f1337patch_new() {
-        let f1337path = F1337Patch::new("test.exe".to_string());
+/// Enum representing the case of the hex digits written when serializing patches.
+/// 
+/// See [F1337Patch::to_string_case].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HexCase {
+    /// Uppercase hex digits, e.g. ``0000000000AF0200:13->37``. This is the default.
+    #[default]
+    Upper,
+    /// Lowercase hex digits, e.g. ``0000000000af0200:13->37``.
+    Lower,
+}
+
+/// Implement [std::fmt::Display] for [HexPatch]
+impl std::fmt::Display for HexPatch {
+    /// This is the implementation of [std::fmt::Display::fmt] for [HexPatch].
+    /// 
+    /// The patch is written as a patch line, e.g. ``0000000000AF0200:13->37``.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:016X}:{:02X}->{:02X}", self.target_address, self.old, self.new)
+    }
+}
+
+/// Enum representing the problems [F1337Patch::diagnose_line] can find on a patch line.
+/// 
+/// See [Variants](#variants) for variants and their meaning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineDiagnostic {
+    /// The line is not 23 characters long. Contains the actual length of the line.
+    WrongLength(usize),
+    /// There is no ``:`` between the address and the old value.
+    MissingColon,
+    /// There is no ``->`` between the old value and the new value.
+    MissingArrow,
+    /// The address contains characters that are not hex digits.
+    NonHexAddress,
+    /// The old value contains characters that are not hex digits.
+    NonHexOld,
+    /// The new value contains characters that are not hex digits.
+    NonHexNew,
+}
+
+/// Enum representing the problems tolerated while parsing a patch file, see [F1337Patch::from_bufreader_with_warnings].
+/// 
+/// See [Variants](#variants) for variants and their meaning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The ``@vN`` version marker names a version newer than [LATEST_FORMAT_VERSION], or version 0.
+    /// The file is parsed with version 1 semantics. Contains the version of the marker.
+    UnknownVersion(u32),
+}
+
+/// Enum representing an edit transforming a [F1337Patch] into another, see [F1337Patch::edit_script].
+/// 
+/// See [Variants](#variants) for variants and their meaning.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditOp {
+    /// A patch to add.
+    Add(HexPatch),
+    /// A patch to remove.
+    Remove(HexPatch),
+    /// A patch to replace by another one at the same address.
+    Modify {
+        /// Address of the modified patch.
+        address: u64,
+        /// The patch before the edit.
+        from: HexPatch,
+        /// The patch after the edit.
+        to: HexPatch,
+    },
+}
+
+/// This is used to represent the estimated I/O cost of applying a [F1337Patch], see [F1337Patch::apply_cost_estimate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyCost {
+    /// Number of seeks.
+    pub seeks: u64,
+    /// Number of bytes read.
+    pub read_bytes: u64,
+    /// Number of bytes written.
+    pub write_bytes: u64,
+}
+
+/// This is used to represent the bytes around a patched byte, see [F1337Patch::context_preview].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytePreview {
+    /// Target address of the patch.
+    pub target_address: u64,
+    /// Bytes right before the target address. Shorter than requested near the start of the target.
+    pub before: Vec<u8>,
+    /// Bytes right after the target address. Shorter than requested near the end of the target.
+    pub after: Vec<u8>,
+    /// Old value of the patch.
+    pub old: u8,
+    /// New value of the patch.
+    pub new: u8,
+}
+
+/// This is used to count how a [F1337Patch] would be applied to a target, see [F1337Patch::preflight].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Preflight {
+    /// Number of patches whose old value is found in the target.
+    pub will_apply: usize,
+    /// Number of patches whose new value is already found in the target.
+    pub already_applied: usize,
+    /// Number of patches whose byte in the target is neither the old nor the new value.
+    pub mismatched: usize,
+    /// Number of patches whose address is past the end of the target.
+    pub out_of_bounds: usize,
+}
+
+/// A patch keyed by section, as a ``(section_name, offset_in_section, old, new)`` tuple, see [F1337Patch::to_section_relative].
+pub type SectionPatch = (String, u64, u8, u8);
+
+/// Enum representing the address width a [F1337Patch] likely targets, see [F1337Patch::likely_address_width].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressWidth {
+    /// Every address fits in 32 bits: the patch likely targets a 32-bit binary.
+    Bits32,
+    /// Some address needs more than 32 bits: the patch targets a 64-bit binary.
+    Bits64,
+}
+
+/// This is used to accumulate statistics while streaming a patch file, see [F1337Patch::stream_apply_stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamStats {
+    /// Number of patches written to the target.
+    pub applied: usize,
+    /// Number of no-op patches, writing their old value back, which were skipped.
+    pub skipped_noop: usize,
+    /// Number of patches whose old value is not found in the target, or whose address is past its end. They are not written.
+    pub mismatched: usize,
+}
+
+/// Enum representing the debugger whose command syntax is emitted by [F1337Patch::to_debugger_script].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebuggerFormat {
+    /// x64dbg, writing a byte with ``mov byte:[0xAF0200], 0x37``.
+    X64dbg,
+    /// WinDbg, writing a byte with ``eb 0xAF0200 0x37``.
+    WinDbg,
+    /// gdb, writing a byte with ``set {char}0xAF0200 = 0x37``.
+    Gdb,
+}
+
+/// Options controlling how a patch file is canonicalized by [F1337Patch::format_file].
+#[derive(Debug, Clone, Copy)]
+pub struct FormatOptions {
+    /// Sort the patches by address, see [F1337Patch::sort_by_address]. Defaults to ``true``.
+    pub sort: bool,
+    /// Remove the duplicate patches, see [F1337Patch::dedup_all]. Defaults to ``true``.
+    pub dedup: bool,
+    /// Case of the hex digits written. Defaults to [HexCase::Upper].
+    pub case: HexCase,
+}
+
+/// Implement [Default] for [FormatOptions]
+impl Default for FormatOptions {
+    /// This is the implementation of [Default::default] for [FormatOptions].
+    fn default() -> Self {
+        FormatOptions {
+            sort: true,
+            dedup: true,
+            case: HexCase::Upper,
+        }
+    }
+}
+
+/// Options controlling how a patch file is parsed.
+/// 
+/// Use [ParseOptions::default] for the strict parsing done by [F1337Patch::from_bufreader].
+/// 
+/// # Example
+/// ```rust
+/// use lib1337patch::ParseOptions;
+/// 
+/// let options = ParseOptions {
+///     lenient: true,
+///     ..ParseOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseOptions<'a> {
+    /// Tolerate whitespace around the ``:`` and ``->`` separators of patch lines.
+    /// 
+    /// When enabled, lines like ``0000000000AF0200 : 13 -> 37`` are accepted.<br/>
+    /// Address, old and new values must still be 16, 2 and 2 hex digits long.
+    pub lenient: bool,
+    /// Accept files that do not start with a ``>`` header line.
+    /// 
+    /// When enabled and the first line does not start with ``>``, it is parsed as a patch line
+    /// and the [target file name](F1337Patch::target_filename) is left empty.
+    pub allow_missing_header: bool,
+    /// Strip every trailing ``\r`` of the lines before validating them.
+    /// 
+    /// Lines ending with ``\r\n`` are always accepted, this also accepts stray carriage returns left by
+    /// files converted back and forth between platforms (e.g. lines ending with ``\r\r\n``).
+    pub normalize_eol: bool,
+    /// Strip a leading byte order mark (``U+FEFF``) from every line before validating it.
+    /// 
+    /// Some tools wrongly prepend a BOM to every line of their exports, not only to the header line.
+    pub strip_bom: bool,
+    /// Prefixes marking comment lines, which are skipped.
+    /// 
+    /// A line is a comment if it starts with one of these prefixes, after trimming its leading whitespace.<br/>
+    /// Defaults to ``#`` and ``;``. Empty prefixes are ignored.
+    pub comment_prefixes: &'a [&'a str],
+    /// Maximum number of patches to read, or [None] for no limit.
+    /// 
+    /// Parsing stops with [PatchFileError::TooManyPatches] as soon as the limit is exceeded,
+    /// which protects against untrusted files with millions of patch lines.
+    pub max_patches: Option<usize>,
+}
+
+/// Implement [Default] for [ParseOptions]
+impl Default for ParseOptions<'_> {
+    /// This is the implementation of [Default::default] for [ParseOptions].
+    /// 
+    /// Default options are strict: every patch line must be exactly in the format described in [F1337Patch].
+    fn default() -> Self {
+        ParseOptions {
+            lenient: false,
+            allow_missing_header: false,
+            normalize_eol: false,
+            strip_bom: false,
+            comment_prefixes: &["#", ";"],
+            max_patches: None,
+        }
+    }
+}
+
+impl ParseOptions<'_> {
+    /// This function checks if ``line`` is a comment according to [ParseOptions::comment_prefixes].
+    fn is_comment(&self, line: &str) -> bool {
+        let line = line.trim_start();
+
+        self.comment_prefixes.iter().any(|prefix| !prefix.is_empty() && line.starts_with(prefix))
+    }
+}
+
+/// This is used to recognize the optional ``@vN`` version marker line of a patch file, which can only follow its header line.
+/// 
+/// Every reader feeds it the lines following the header line, so that they all agree on where the marker is allowed.
+struct VersionMarker {
+    /// Whether the next line is the first line after the header line.
+    after_header: bool,
+    /// Version read from the marker line, if any.
+    version: Option<u32>,
+    /// Unknown versions found in the marker line.
+    warnings: Vec<ParseWarning>,
+}
+
+impl VersionMarker {
+    /// This function creates a [VersionMarker] expecting the first line after the header line.
+    fn new() -> Self {
+        VersionMarker {
+            after_header: true,
+            version: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// This function consumes ``line`` if it is the version marker line, returning whether it was.
+    fn take(&mut self, line: &str) -> Result<bool, PatchFileError> {
+        if !std::mem::take(&mut self.after_header) {
+            return Ok(false);
+        }
+        self.version = F1337Patch::parse_version_marker(line, &mut self.warnings)?;
+        Ok(self.version.is_some())
+    }
+
+    /// This function returns the format version read from the marker line, 1 if there is none.
+    fn version(&self) -> u32 {
+        self.version.unwrap_or(1)
+    }
+}
+
+/// This is used to create representation of the patch file.
+/// 
+/// Path files are in the following format:<br/>
+/// The first line start with ``>`` and followed by the target file name.
+/// 
+/// The rest of the lines are patches in the following format:<br/>
+/// [``TargetAddress``](HexPatch::target_address):[``Old``](HexPatch::old)->[``New``](HexPatch::new) all in HEX in a TXT file.
+/// 
+/// Target address is always 16 hex digits long, old value and new value are always 2 hex digits long.
+/// 
+/// A patch line may also fill several bytes, such as ``0000000000AF0200:00->90*16`` (see [FillPatch]).
+/// It is read as one patch per filled byte, so its old value must be given: ``*`` is rejected with [PatchFileError::WrongFormat].
+/// 
+/// The header line may be followed by a ``@vN`` line, giving the [format version](F1337Patch::format_version) ``N`` of the file.
+/// 
+/// Lines starting with ``#`` or ``;`` are comments and are skipped when parsing (see [ParseOptions::comment_prefixes]).
+/// 
+/// # Example
+/// ```text
+/// >test.exe
+/// # Skip the license check.
+/// 0000000000AF0200:13->37
+/// 0000000000AF0206:37->37
+/// ```
+/// 
+/// Patches are stored in a vector of [HexPatch].
+#[derive(Debug, PartialEq)]
+pub struct F1337Patch {
+    /// Target file name. Extracted from the first line of the patch file.
+    pub target_filename: String,
+    /// Vector of patches. Builded from extracted data from the rest of the lines of the patch file.
+    pub patches: Vec<HexPatch>,
+    /// Version of the patch format the patch set follows, see [F1337Patch::format_version].
+    format_version: u32,
+}
+
+impl F1337Patch {
+    /// This creates an new empty [F1337Patch].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use lib1337patch::HexPatch;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let patch = HexPatch::new(0x0000000000AF0200, 0x13, 0x37);
+    /// 
+    /// f1337patch.add_patch(patch);
+    /// 
+    /// println!("File name : {}", f1337patch.target_filename);
+    /// println!("Patches : {:?}", f1337patch.patches);
+    /// ```
+    pub fn new(target_filename: String) -> Self {
+        F1337Patch {
+            target_filename,
+            patches: Vec::new(),
+            format_version: 1,
+        }
+    }
+
+    /// This generates a [F1337Patch] of ``count`` pseudo-random patches, deterministically from ``seed``.
+    /// 
+    /// The same seed always yields the same patches, which makes tests and benchmarks reproducible.<br/>
+    /// Addresses fit in 32 bits, and old and new values are any bytes. The generator is a small SplitMix64,
+    /// which is not suitable for anything but fixtures.
+    /// 
+    /// # Arguments
+    /// - ``filename``: The target file name.
+    /// - ``count``: The number of patches to generate.
+    /// - ``seed``: The seed of the generator.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let f1337patch = F1337Patch::random_fixture("test.exe", 100, 1337);
+    /// 
+    /// assert_eq!(f1337patch.patches.len(), 100);
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn random_fixture(filename: &str, count: usize, seed: u64) -> F1337Patch {
+        let mut f1337patch = F1337Patch::new(filename.to_string());
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        };
+
+        f1337patch.patches = (0..count)
+            .map(|_| {
+                let value = next();
+                HexPatch::new(value >> 32, value as u8, (value >> 8) as u8)
+            })
+            .collect();
+        f1337patch
+    }
+
+    /// This returns the version of the patch format the patch set follows.
+    /// 
+    /// It starts at 1, see [LATEST_FORMAT_VERSION], and is read from the ``@vN`` marker line of the patch file, if any.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// assert_eq!(f1337patch.format_version(), 1);
+    /// ```
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    /// This upgrades the patch set to the format ``version``.
+    /// 
+    /// Upgrading migrates the patches to the newer format. Version 1 is the only version so far,
+    /// so there is nothing to migrate yet: upgrading to the current version does nothing.
+    /// 
+    /// # Arguments
+    /// - ``version``: The format version to upgrade to.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::UnsupportedVersion] if ``version`` is unknown or older than [F1337Patch::format_version]. Contains ``version``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.upgrade_to(1).unwrap();
+    /// assert!(f1337patch.upgrade_to(2).is_err());
+    /// ```
+    pub fn upgrade_to(&mut self, version: u32) -> Result<(), PatchFileError> {
+        if version > LATEST_FORMAT_VERSION || version < self.format_version {
+            return Err(PatchFileError::UnsupportedVersion(version));
+        }
+        self.format_version = version;
+        Ok(())
+    }
+
+    /// This adds a patch to the [F1337Patch].
+    /// 
+    /// To create a [HexPatch], use [HexPatch::new].
+    /// 
+    /// # Arguments
+    /// - ``patch``: A [HexPatch]. Can be created with [HexPatch::new].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use lib1337patch::HexPatch;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// println!("File name : {}", f1337patch.target_filename);
+    /// ```
+    pub fn add_patch(&mut self, patch: HexPatch) {
+        self.patches.push(patch);
+    }
+
+    /// This adds a patch to the [F1337Patch], unless it conflicts with an existing patch.
+    /// 
+    /// A patch conflicts if a patch at the same address writes a different new value.<br/>
+    /// Adding patches with this function keeps the patch set valid for [F1337Patch::validate] at every step.
+    /// 
+    /// # Arguments
+    /// - ``patch``: A [HexPatch]. Can be created with [HexPatch::new].
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConflictingPatch] if the patch conflicts with an existing patch. Contains the address.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, PatchFileError};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.try_add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37)).unwrap();
+    /// assert_eq!(
+    ///     f1337patch.try_add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x38)),
+    ///     Err(PatchFileError::ConflictingPatch(0x0000000000AF0200))
+    /// );
+    /// ```
+    pub fn try_add_patch(&mut self, patch: HexPatch) -> Result<(), PatchFileError> {
+        if self.patches.iter().any(|existing| existing.target_address == patch.target_address && existing.new != patch.new) {
+            return Err(PatchFileError::ConflictingPatch(patch.target_address));
+        }
+        self.patches.push(patch);
+        Ok(())
+    }
+
+    /// This inserts a patch at ``index`` in the [F1337Patch], shifting the following patches.
+    /// 
+    /// Unlike [F1337Patch::add_patch], this keeps a desired order without re-sorting.
+    /// 
+    /// # Arguments
+    /// - ``index``: The position of the patch in [F1337Patch::patches] after the insertion.
+    /// - ``patch``: A [HexPatch]. Can be created with [HexPatch::new].
+    /// 
+    /// # Panics
+    /// Panics if ``index`` is greater than the number of patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// f1337patch.insert_patch(0, HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// ```
+    pub fn insert_patch(&mut self, index: usize, patch: HexPatch) {
+        assert!(
+            index <= self.patches.len(),
+            "cannot insert patch at index {} in a patch set of {} patches",
+            index,
+            self.patches.len(),
+        );
+        self.patches.insert(index, patch);
+    }
+
+    /// This returns the patch at ``index``, or [None] if ``index`` is out of range.
+    /// 
+    /// # Arguments
+    /// - ``index``: The index of the patch in [F1337Patch::patches].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.get(0).is_some());
+    /// assert!(f1337patch.get(1).is_none());
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&HexPatch> {
+        self.patches.get(index)
+    }
+
+    /// This adds one ``NOP`` patch per byte of ``old_bytes``, starting at ``start``.
+    /// 
+    /// Each patch is created with [HexPatch::nop], the n-th byte of ``old_bytes`` being the old value at ``start + n``.
+    /// 
+    /// # Arguments
+    /// - ``start``: The address of the first byte to NOP out.
+    /// - ``old_bytes``: The current bytes of the run to NOP out.
+    /// 
+    /// # Returns
+    /// - Result of ``()`` or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::AddressOverflow] if the last byte of the run is out of the addressable range. Contains ``start``.
+    ///   No patch is added.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// // Remove a ``jz short`` instruction.
+    /// f1337patch.nop_range(0x0000000000AF0200, &[0x74, 0x05]).unwrap();
+    /// assert_eq!(f1337patch.patches.len(), 2);
+    /// ```
+    pub fn nop_range(&mut self, start: u64, old_bytes: &[u8]) -> Result<(), PatchFileError> {
+        if !old_bytes.is_empty() && start.checked_add(old_bytes.len() as u64 - 1).is_none() {
+            return Err(PatchFileError::AddressOverflow(start));
+        }
+
+        for (offset, old) in old_bytes.iter().enumerate() {
+            self.patches.push(HexPatch::nop(start + offset as u64, *old));
+        }
+        Ok(())
+    }
+
+    /// This returns a new [F1337Patch] without the patches already applied by ``baseline``.
+    /// 
+    /// A patch is already applied by ``baseline`` if it contains a patch with the same address and the same new value.<br/>
+    /// This avoids re-declaring patches a base layer already applies.
+    /// 
+    /// # Arguments
+    /// - ``baseline``: A reference to the base layer [F1337Patch].
+    /// 
+    /// # Returns
+    /// - A new [F1337Patch] with the same target file name and the remaining patches, in their original order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut baseline = F1337Patch::new("test.exe".to_string());
+    /// let mut layer = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// baseline.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// layer.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// layer.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+    /// 
+    /// assert_eq!(layer.without_redundant(&baseline).patches.len(), 1);
+    /// ```
+    pub fn without_redundant(&self, baseline: &F1337Patch) -> F1337Patch {
+        let mut f1337patch = F1337Patch::new(self.target_filename.clone());
+
+        f1337patch.format_version = self.format_version;
+        f1337patch.patches = self.patches.iter()
+            .filter(|patch| !baseline.patches.iter().any(|base| {
+                base.target_address == patch.target_address && base.new == patch.new
+            }))
+            .copied()
+            .collect();
+        f1337patch
+    }
+
+    /// This splits the [F1337Patch] in two according to a predicate.
+    /// 
+    /// # Arguments
+    /// - ``pred``: A closure returning ``true`` for the patches to put in the first [F1337Patch].
+    /// 
+    /// # Returns
+    /// - A tuple of the matching and the non-matching patches, both with the same target file name, in their original order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, X86_NOP};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::nop(0x0000000000AF0200, 0x74));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+    /// 
+    /// let (nops, others) = f1337patch.partition_by(|patch| patch.new == X86_NOP);
+    /// ```
+    pub fn partition_by<F: Fn(&HexPatch) -> bool>(&self, pred: F) -> (F1337Patch, F1337Patch) {
+        let mut matching = F1337Patch::new(self.target_filename.clone());
+        let mut others = F1337Patch::new(self.target_filename.clone());
+
+        (matching.format_version, others.format_version) = (self.format_version, self.format_version);
+        (matching.patches, others.patches) = self.patches.iter().partition(|patch| pred(patch));
+        (matching, others)
+    }
+
+    /// This returns the gaps between consecutive patch addresses, in ascending address order.
+    /// 
+    /// The n-th gap is the difference between the (n+1)-th and the n-th smallest addresses,
+    /// which helps deciding how to batch reads and writes when applying.
+    /// 
+    /// # Returns
+    /// - A [Vec] of gaps, with one element less than there are patches (empty if there are less than two patches).
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x110, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x100, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.address_gaps(), vec![0x10]);
+    /// ```
+    pub fn address_gaps(&self) -> Vec<u64> {
+        let mut addresses: Vec<u64> = self.patches.iter().map(|patch| patch.target_address).collect();
+
+        addresses.sort_unstable();
+        addresses.windows(2).map(|pair| pair[1] - pair[0]).collect()
+    }
+
+    /// This swaps the old and new values of the patches matching a predicate.
+    /// 
+    /// Reversing a patch turns it into the patch undoing it, so this undoes a subset of the patches.
+    /// 
+    /// # Arguments
+    /// - ``pred``: A closure returning ``true`` for the patches to reverse.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.reverse_where(|patch| patch.target_address >= 0x0000000000AF0200);
+    /// assert_eq!(f1337patch.patches[0], HexPatch::new(0x0000000000AF0200, 0x37, 0x13));
+    /// ```
+    pub fn reverse_where<F: Fn(&HexPatch) -> bool>(&mut self, pred: F) {
+        for patch in self.patches.iter_mut().filter(|patch| pred(patch)) {
+            std::mem::swap(&mut patch.old, &mut patch.new);
+        }
+    }
+
+    /// This returns the reverse [F1337Patch], undoing the patches.
+    /// 
+    /// The old and new values of every patch are swapped, and the patches are in reverse order,
+    /// so that patches at the same address are undone from the last one to the first one.
+    /// 
+    /// # Returns
+    /// - A new [F1337Patch] with the same target file name and the reverse patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.reversed().patches, vec![HexPatch::new(0x0000000000AF0200, 0x37, 0x13)]);
+    /// ```
+    pub fn reversed(&self) -> F1337Patch {
+        let mut f1337patch = F1337Patch::new(self.target_filename.clone());
+
+        f1337patch.format_version = self.format_version;
+        f1337patch.patches = self.patches.iter().rev().map(|patch| HexPatch::new(patch.target_address, patch.new, patch.old)).collect();
+        f1337patch
+    }
+
+    /// This checks if every patch address falls within the same page.
+    /// 
+    /// If so, the whole patch can be applied with a single read-modify-write of that page.
+    /// 
+    /// # Arguments
+    /// - ``page_size``: The size of a page in bytes, e.g. ``0x1000``.
+    /// 
+    /// # Returns
+    /// - ``true`` if all addresses are in one page, or if there are no patches.
+    /// 
+    /// # Panics
+    /// Panics if ``page_size`` is 0.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// 
+    /// assert!(f1337patch.is_single_page(0x1000));
+    /// ```
+    pub fn is_single_page(&self, page_size: u64) -> bool {
+        assert!(page_size != 0, "page size must not be 0");
+
+        let mut pages = self.patches.iter().map(|patch| patch.target_address / page_size);
+
+        match pages.next() {
+            Some(first) => pages.all(|page| page == first),
+            None => true,
+        }
+    }
+
+    /// This validates the [F1337Patch] before it is serialized or applied.
+    /// 
+    /// It checks that:
+    /// - the target file name is not empty,
+    /// - no two patches write different new values at the same address.
+    /// 
+    /// Every patch writes a single byte, so no patch is out of range: any address, up to [u64::MAX], and any values are valid.
+    /// 
+    /// # Returns
+    /// - Result of [()] or the first [PatchFileError] found, in patch order.
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidHeader] if the target file name is empty.
+    /// - [PatchFileError::ConflictingPatch] if two patches write different values at the same address. Contains the address.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, PatchFileError};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.validate().unwrap();
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x38));
+    /// assert_eq!(f1337patch.validate(), Err(PatchFileError::ConflictingPatch(0x0000000000AF0200)));
+    /// ```
+    pub fn validate(&self) -> Result<(), PatchFileError> {
+        let mut new_values = HashMap::new();
+
+        Self::check_header_format(&format!(">{}", self.target_filename))?;
+        for patch in &self.patches {
+            if *new_values.entry(patch.target_address).or_insert(patch.new) != patch.new {
+                return Err(PatchFileError::ConflictingPatch(patch.target_address));
+            }
+        }
+        Ok(())
+    }
+
+    /// This validates the patches with a caller-supplied ``validator``, for semantic checks this crate does not know about.
+    /// 
+    /// For instance, a disassembler-backed validator can check that the old value of each patch is a plausible instruction byte at its address.
+    /// 
+    /// # Arguments
+    /// - ``validator``: A function returning ``true`` if a patch is valid.
+    /// 
+    /// # Returns
+    /// - The indices of the patches rejected by ``validator``, in order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x74, 0xEB));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.validate_with(|patch| patch.old == 0x74), vec![1]);
+    /// ```
+    pub fn validate_with<F: Fn(&HexPatch) -> bool>(&self, validator: F) -> Vec<usize> {
+        self.patches.iter()
+            .enumerate()
+            .filter(|(_, patch)| !validator(patch))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// This checks the new value of every patch against the ``allowed`` bytes, to enforce a patching policy.
+    /// 
+    /// For instance, allowing only [X86_NOP] and ``0xEB`` restricts the patches to nopping code and forcing short jumps,
+    /// rejecting arbitrary byte writes.
+    /// 
+    /// # Arguments
+    /// - ``allowed``: The new values allowed.
+    /// 
+    /// # Returns
+    /// - The indices of the patches whose new value is not allowed, in order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, X86_NOP};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::nop(0x0000000000AF0200, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.validate_new_allowlist(&[X86_NOP]), vec![1]);
+    /// ```
+    pub fn validate_new_allowlist(&self, allowed: &[u8]) -> Vec<usize> {
+        self.validate_with(|patch| allowed.contains(&patch.new))
+    }
+
+    /// This checks that no two patches target the same address, whatever their values.
+    /// 
+    /// This is stricter than [F1337Patch::validate], which allows duplicate patches writing the same new value.<br/>
+    /// Some applier backends require a single patch per address.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::DuplicateAddress] if an address is patched more than once. Contains the first repeated address, in patch order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, PatchFileError};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.require_unique_addresses().unwrap();
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// assert_eq!(f1337patch.require_unique_addresses(), Err(PatchFileError::DuplicateAddress(0x0000000000AF0200)));
+    /// ```
+    pub fn require_unique_addresses(&self) -> Result<(), PatchFileError> {
+        let mut addresses = std::collections::HashSet::new();
+
+        match self.patches.iter().find(|patch| !addresses.insert(patch.target_address)) {
+            Some(patch) => Err(PatchFileError::DuplicateAddress(patch.target_address)),
+            None => Ok(()),
+        }
+    }
+
+    /// This checks that the [F1337Patch] is internally consistent, as a single gate before trusting a patch set.
+    /// 
+    /// A patch set is consistent if [F1337Patch::validate] succeeds and no patch is a no-op, i.e. writes its old value back.<br/>
+    /// Every [HexPatch] covers a single byte, so patches cannot partially overlap: overlaps are conflicts on the same address.
+    /// 
+    /// # Returns
+    /// - ``true`` if the patch set is consistent.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// assert!(f1337patch.is_consistent());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// assert!(!f1337patch.is_consistent());
+    /// ```
+    pub fn is_consistent(&self) -> bool {
+        self.validate().is_ok() && self.patches.iter().all(|patch| patch.old != patch.new)
+    }
+
+    /// This returns a new [F1337Patch] with at most ``n`` patches taken at an even stride across the patches.
+    /// 
+    /// The i-th sampled patch is the patch at index ``i * len / n``, so a sample of 2 out of 10 patches keeps the patches 0 and 5.<br/>
+    /// If ``n`` is greater than the number of patches, all of them are kept.<br/>
+    /// This is handy to smoke-test an apply pipeline against huge patch files.
+    /// 
+    /// # Arguments
+    /// - ``n``: The maximum number of patches to keep.
+    /// 
+    /// # Returns
+    /// - A new [F1337Patch] with the same target file name and the sampled patches, in their original order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.nop_range(0x0000000000AF0200, &[0x00; 100]).unwrap();
+    /// 
+    /// assert_eq!(f1337patch.sample(10).patches.len(), 10);
+    /// ```
+    pub fn sample(&self, n: usize) -> F1337Patch {
+        let mut f1337patch = F1337Patch::new(self.target_filename.clone());
+        let len = self.patches.len();
+        let count = n.min(len);
+
+        f1337patch.format_version = self.format_version;
+        f1337patch.patches = (0..count).map(|i| self.patches[i * len / count]).collect();
+        f1337patch
+    }
+
+    /// This converts the patch addresses from absolute addresses to RVAs (relative virtual addresses) by subtracting ``image_base``.
+    /// 
+    /// The patches are left unchanged if any address is below ``image_base``.
+    /// 
+    /// # Arguments
+    /// - ``image_base``: The base address the module is loaded at.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::AddressOverflow] if an address is below ``image_base``. Contains the first such address.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000140001000, 0x13, 0x37));
+    /// 
+    /// f1337patch.to_rva(0x0000000140000000).unwrap();
+    /// assert_eq!(f1337patch.patches[0].target_address, 0x1000);
+    /// ```
+    pub fn to_rva(&mut self, image_base: u64) -> Result<(), PatchFileError> {
+        self.map_addresses(|address| address.checked_sub(image_base))
+    }
+
+    /// This converts the patch addresses from RVAs (relative virtual addresses) to absolute addresses by adding ``image_base``.
+    /// 
+    /// The patches are left unchanged if any address overflows.
+    /// 
+    /// # Arguments
+    /// - ``image_base``: The base address the module is loaded at.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::AddressOverflow] if adding ``image_base`` to an address overflows. Contains the first such address.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x1000, 0x13, 0x37));
+    /// 
+    /// f1337patch.to_absolute(0x0000000140000000).unwrap();
+    /// assert_eq!(f1337patch.patches[0].target_address, 0x0000000140001000);
+    /// ```
+    pub fn to_absolute(&mut self, image_base: u64) -> Result<(), PatchFileError> {
+        self.map_addresses(|address| address.checked_add(image_base))
+    }
+
+    /// This function replaces every address by the result of ``map``, only if ``map`` succeeds for all of them.
+    fn map_addresses<F: Fn(u64) -> Option<u64>>(&mut self, map: F) -> Result<(), PatchFileError> {
+        let addresses = self.patches.iter()
+            .map(|patch| map(patch.target_address).ok_or(PatchFileError::AddressOverflow(patch.target_address)))
+            .collect::<Result<Vec<u64>, PatchFileError>>()?;
+
+        for (patch, address) in self.patches.iter_mut().zip(addresses) {
+            patch.target_address = address;
+        }
+        Ok(())
+    }
+
+    /// This returns the ``(address, old)`` pairs of the patches, sorted by address.
+    /// 
+    /// This is the signature of the bytes the patch expects, which can be compared against bytes read
+    /// from a binary to match a patch across binary versions.
+    /// 
+    /// # Returns
+    /// - A [Vec] of ``(address, old)`` pairs, sorted by address. Patches at the same address keep their original order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.old_signature(), vec![(0x0000000000AF0200, 0x13), (0x0000000000AF0206, 0x37)]);
+    /// ```
+    pub fn old_signature(&self) -> Vec<(u64, u8)> {
+        let mut signature: Vec<(u64, u8)> = self.patches.iter().map(|patch| (patch.target_address, patch.old)).collect();
+
+        signature.sort_by_key(|(address, _)| *address);
+        signature
+    }
+
+    /// This groups the patch indices by the 8-byte aligned word they fall in.
+    /// 
+    /// The word index of a patch is ``address / 8``. Words containing several patches can be patched with a single 8-byte write.
+    /// 
+    /// # Returns
+    /// - A [BTreeMap] of word index to the indices into [F1337Patch::patches] of the patches within that word, in ascending order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.patches_per_word()[&(0x0000000000AF0200 / 8)], vec![0, 1]);
+    /// ```
+    pub fn patches_per_word(&self) -> BTreeMap<u64, Vec<usize>> {
+        let mut words: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+
+        for (index, patch) in self.patches.iter().enumerate() {
+            words.entry(patch.target_address / 8).or_default().push(index);
+        }
+        words
+    }
+
+    /// This checks if the patches are the same list of patches repeated twice.
+    /// 
+    /// This detects the common mistake of pasting or concatenating the same patch file twice.
+    /// 
+    /// # Returns
+    /// - ``true`` if there are patches and the second half is exactly the first half.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.is_doubled());
+    /// ```
+    pub fn is_doubled(&self) -> bool {
+        let (first_half, second_half) = self.patches.split_at(self.patches.len() / 2);
+
+        !self.patches.is_empty() && first_half == second_half
+    }
+
+    /// This serializes the [F1337Patch] in the patch file format, with hex digits in the given case.
+    /// 
+    /// With [HexCase::Upper], this is the same as [ToString::to_string]. The target file name is written as is.<br/>
+    /// Both cases are read back by the parser, which does not care about the case of hex digits.
+    /// 
+    /// # Arguments
+    /// - ``case``: The [HexCase] of the addresses and values.
+    /// 
+    /// # Returns
+    /// - The serialized [F1337Patch].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexCase, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x3A));
+    /// 
+    /// assert_eq!(f1337patch.to_string_case(HexCase::Lower), ">test.exe\n0000000000af0200:13->3a\n");
+    /// ```
+    pub fn to_string_case(&self, case: HexCase) -> String {
+        let mut serialized = format!(">{}\n", self.target_filename);
+
+        for patch in &self.patches {
+            serialized.push_str(&patch.to_string_case(case));
+            serialized.push('\n');
+        }
+        serialized
+    }
+
+    /// This serializes the [F1337Patch] in the patch file format, with or without a new line after the last line.
+    /// 
+    /// Some tools require, or forbid, a trailing new line. [ToString::to_string] always writes it, like ``to_string_opts(true)``.
+    /// 
+    /// # Arguments
+    /// - ``trailing_newline``: Whether the output ends with ``\n``.
+    /// 
+    /// # Returns
+    /// - The serialized [F1337Patch].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.to_string_opts(false), ">test.exe\n0000000000AF0200:13->37");
+    /// ```
+    pub fn to_string_opts(&self, trailing_newline: bool) -> String {
+        let mut serialized = self.to_string();
+
+        if !trailing_newline {
+            serialized.pop();
+        }
+        serialized
+    }
+
+    /// This serializes the [F1337Patch] in the patch file format, skipping no-op patches, i.e. patches writing their old value back.
+    /// 
+    /// This produces a minimal patch file for sharing, which the parser reads back as the effective patches.
+    /// 
+    /// # Returns
+    /// - The serialized [F1337Patch], without its no-op patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.to_string_effective(), ">test.exe\n0000000000AF0200:13->37\n");
+    /// ```
+    pub fn to_string_effective(&self) -> String {
+        let mut serialized = format!(">{}\n", self.target_filename);
+
+        for patch in self.patches.iter().filter(|patch| patch.old != patch.new) {
+            serialized.push_str(&format!("{}\n", patch));
+        }
+        serialized
+    }
+
+    /// This returns the patch whose address is the closest to ``address``.
+    /// 
+    /// When two patches are at the same distance, the one with the lower address is returned.<br/>
+    /// When several patches target that address, the first one is returned.
+    /// 
+    /// # Arguments
+    /// - ``address``: The address to look around.
+    /// 
+    /// # Returns
+    /// - The closest [HexPatch], or [None] if there are no patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x100, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.nearest_to(0x1C0).unwrap().target_address, 0x200);
+    /// ```
+    pub fn nearest_to(&self, address: u64) -> Option<&HexPatch> {
+        self.patches.iter().min_by_key(|patch| (patch.target_address.abs_diff(address), patch.target_address))
+    }
+
+    /// This computes the edits transforming the patches of ``self`` into the patches of ``desired``.
+    /// 
+    /// Patches are matched by address: a patch is added or removed if its address is only in ``desired`` or only in ``self``,
+    /// and modified if both have a different patch at that address. When several patches target the same address,
+    /// the last one wins, like when they are applied.
+    /// 
+    /// # Arguments
+    /// - ``desired``: A reference to the [F1337Patch] to transform ``self`` into.
+    /// 
+    /// # Returns
+    /// - A [Vec] of [EditOp], sorted by address. Empty if both have the same patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{EditOp, F1337Patch, HexPatch};
+    /// 
+    /// let current = F1337Patch::new("test.exe".to_string());
+    /// let mut desired = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// desired.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(current.edit_script(&desired), vec![EditOp::Add(HexPatch::new(0x0000000000AF0200, 0x13, 0x37))]);
+    /// ```
+    pub fn edit_script(&self, desired: &F1337Patch) -> Vec<EditOp> {
+        let current = self.last_patch_per_address();
+        let mut desired = desired.last_patch_per_address();
+        let mut edits = Vec::new();
+
+        for (address, from) in current {
+            match desired.remove(&address) {
+                None => edits.push(EditOp::Remove(from)),
+                Some(to) if to != from => edits.push(EditOp::Modify { address, from, to }),
+                Some(_) => (),
+            }
+        }
+        edits.extend(desired.into_values().map(EditOp::Add));
+        edits.sort_by_key(|edit| match edit {
+            EditOp::Add(patch) | EditOp::Remove(patch) => patch.target_address,
+            EditOp::Modify { address, .. } => *address,
+        });
+        edits
+    }
+
+    /// This function maps each address to the last patch targeting it.
+    fn last_patch_per_address(&self) -> BTreeMap<u64, HexPatch> {
+        self.patches.iter().map(|patch| (patch.target_address, *patch)).collect()
+    }
+
+    /// This estimates the I/O cost of applying the patches with a batched strategy.
+    /// 
+    /// Patches are sorted by address and grouped in blocks: a block starts at the lowest address not yet grouped
+    /// and contains every patch less than ``block_size`` bytes after it.<br/>
+    /// Each block is read at once and written back at once, which costs two seeks and the span of the block
+    /// (from its first to its last patched byte) in read and written bytes.
+    /// 
+    /// This helps choosing between patching byte by byte, in blocks or through a memory map.
+    /// 
+    /// # Arguments
+    /// - ``block_size``: The maximum span of a block in bytes. A ``block_size`` of 0 is treated as 1.
+    /// 
+    /// # Returns
+    /// - The estimated [ApplyCost].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// 
+    /// let cost = f1337patch.apply_cost_estimate(0x1000);
+    /// assert_eq!(cost.seeks, 2);
+    /// assert_eq!(cost.read_bytes, 7);
+    /// ```
+    pub fn apply_cost_estimate(&self, block_size: u64) -> ApplyCost {
+        let mut addresses: Vec<u64> = self.patches.iter().map(|patch| patch.target_address).collect();
+        let mut cost = ApplyCost { seeks: 0, read_bytes: 0, write_bytes: 0 };
+        let mut block: Option<(u64, u64)> = None;
+        let close_block = |cost: &mut ApplyCost, (start, end): (u64, u64)| {
+            cost.seeks += 2;
+            cost.read_bytes += end - start + 1;
+            cost.write_bytes += end - start + 1;
+        };
+
+        addresses.sort_unstable();
+        for address in addresses {
+            block = match block {
+                Some((start, _)) if address - start < block_size.max(1) => Some((start, address)),
+                Some(previous) => {
+                    close_block(&mut cost, previous);
+                    Some((address, address))
+                },
+                None => Some((address, address)),
+            };
+        }
+        if let Some(last) = block {
+            close_block(&mut cost, last);
+        }
+        cost
+    }
+
+    /// This groups the patch indices by the caller-supplied named regions they fall in, e.g. known functions.
+    /// 
+    /// A patch falls in a region ``(start, end, name)`` if ``start <= address < end``. A patch falling in several
+    /// regions is listed in each of them. Patches outside every region are listed under the empty name ``""``.
+    /// 
+    /// # Arguments
+    /// - ``regions``: The ``(start, end, name)`` regions.
+    /// 
+    /// # Returns
+    /// - A [BTreeMap] of region name to the indices into [F1337Patch::patches] of the patches inside it, in ascending order.<br/>
+    ///   Every named region is present, even without patches. The ``""`` entry is only present if some patches are outside every region.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x1004, 0x74, 0xEB));
+    /// 
+    /// let regions = f1337patch.assign_regions(&[(0x1000, 0x1100, "check_license".to_string())]);
+    /// assert_eq!(regions["check_license"], vec![0]);
+    /// ```
+    pub fn assign_regions(&self, regions: &[(u64, u64, String)]) -> BTreeMap<String, Vec<usize>> {
+        let mut assigned: BTreeMap<String, Vec<usize>> = regions.iter().map(|(_, _, name)| (name.clone(), Vec::new())).collect();
+
+        for (index, patch) in self.patches.iter().enumerate() {
+            let mut inside = regions.iter()
+                .filter(|(start, end, _)| (*start..*end).contains(&patch.target_address))
+                .peekable();
+
+            if inside.peek().is_none() {
+                assigned.entry(String::new()).or_default().push(index);
+            }
+            for (_, _, name) in inside {
+                if let Some(indices) = assigned.get_mut(name) {
+                    indices.push(index);
+                }
+            }
+        }
+        assigned
+    }
+
+    /// This converts the patches to a relocatable form, keyed by section and offset in the section.
+    /// 
+    /// Each section is a ``(name, base, size)`` tuple covering the addresses from ``base`` to ``base + size``, end excluded.<br/>
+    /// A patch is mapped to the first section containing its address. Patches keyed by section survive a relayout of the target
+    /// shifting the section bases, unlike patches at absolute addresses.
+    /// 
+    /// # Arguments
+    /// - ``sections``: The ``(name, base, size)`` sections of the target.
+    /// 
+    /// # Returns
+    /// - A tuple of the [SectionPatch] patches, in patch order,
+    ///   and of the patches outside all sections, in patch order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000001010, 0x13, 0x37));
+    /// 
+    /// let (relative, outside) = f1337patch.to_section_relative(&[(".text".to_string(), 0x1000, 0x200)]);
+    /// assert_eq!(relative, vec![(".text".to_string(), 0x10, 0x13, 0x37)]);
+    /// assert!(outside.is_empty());
+    /// ```
+    pub fn to_section_relative(&self, sections: &[(String, u64, u64)]) -> (Vec<SectionPatch>, Vec<HexPatch>) {
+        let mut relative = Vec::new();
+        let mut outside = Vec::new();
+
+        for patch in &self.patches {
+            let section = sections.iter().find(|(_, base, size)| {
+                patch.target_address >= *base && patch.target_address - base < *size
+            });
+
+            match section {
+                Some((name, base, _)) => relative.push((name.clone(), patch.target_address - base, patch.old, patch.new)),
+                None => outside.push(*patch),
+            }
+        }
+        (relative, outside)
+    }
+
+    /// This counts the patches turning an x86 conditional jump into an unconditional one.
+    /// 
+    /// A patch is counted if its ``(old, new)`` values are one of the [JUMP_PATCH_TRANSITIONS].<br/>
+    /// This gives a quick insight into the nature of a patch, e.g. for reverse engineering statistics.
+    /// 
+    /// # Returns
+    /// - The number of jump patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x74, 0xEB));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.count_jump_patches(), 1);
+    /// ```
+    pub fn count_jump_patches(&self) -> usize {
+        self.patches.iter()
+            .filter(|patch| JUMP_PATCH_TRANSITIONS.contains(&(patch.old, patch.new)))
+            .count()
+    }
+
+    /// This describes the [F1337Patch] in a single human-readable line, for logging.
+    /// 
+    /// The line contains the target file name, the number of patches, split between effective patches and no-ops
+    /// (patches writing their old value back), and the lowest and highest patched addresses.
+    /// 
+    /// # Returns
+    /// - The description, e.g. ``test.exe: 5 patches (4 effective, 1 no-op), addresses 0xAF0200..0xAF0210``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.describe(), "test.exe: 2 patches (1 effective, 1 no-op), addresses 0xAF0200..0xAF0206");
+    /// ```
+    pub fn describe(&self) -> String {
+        let no_ops = self.patches.iter().filter(|patch| patch.old == patch.new).count();
+        let mut description = format!(
+            "{}: {} {} ({} effective, {} {})",
+            self.target_filename,
+            self.patches.len(),
+            match self.patches.len() {
+                1 => "patch",
+                _ => "patches",
+            },
+            self.patches.len() - no_ops,
+            no_ops,
+            match no_ops {
+                1 => "no-op",
+                _ => "no-ops",
+            },
+        );
+        let addresses = self.patches.iter().map(|patch| patch.target_address);
+
+        if let (Some(lowest), Some(highest)) = (addresses.clone().min(), addresses.max()) {
+            description.push_str(&format!(", addresses {:#X}..{:#X}", lowest, highest));
+        }
+        description
+    }
+
+    /// This exports the patches as sparse write operations, for binary update tooling such as bsdiff-based updaters.
+    /// 
+    /// Each operation is an ``(offset, bytes)`` pair writing ``bytes`` at ``offset``. Operations are sorted by offset,
+    /// and patches at adjacent addresses are coalesced into a single operation.<br/>
+    /// When several patches target the same address, the last one wins, as it would when applying the patches in order.
+    /// 
+    /// # Returns
+    /// - The write operations, sorted by offset and not overlapping.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0201, 0x37, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.to_sparse_ops(), vec![(0x0000000000AF0200, vec![0x37, 0x13])]);
+    /// ```
+    pub fn to_sparse_ops(&self) -> Vec<(u64, Vec<u8>)> {
+        let mut ops: Vec<(u64, Vec<u8>)> = Vec::new();
+
+        for (address, patch) in self.last_patch_per_address() {
+            match ops.last_mut() {
+                Some((offset, bytes)) if *offset + bytes.len() as u64 == address => bytes.push(patch.new),
+                _ => ops.push((address, vec![patch.new])),
+            }
+        }
+        ops
+    }
+
+    /// This converts the patches into write-memory commands for a debugger, to apply them to a live process from its script interface.
+    /// 
+    /// One command is emitted per patch, in order, each on its own line.<br/>
+    /// Addresses are written as is: they are file offsets, so they must be rebased on the loaded module by the caller if needed.
+    /// 
+    /// # Arguments
+    /// - ``format`` - The debugger whose command syntax is used.
+    /// 
+    /// # Returns
+    /// - The script, with a trailing newline after each command.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{DebuggerFormat, F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.to_debugger_script(DebuggerFormat::WinDbg), "eb 0xAF0200 0x37\n");
+    /// ```
+    pub fn to_debugger_script(&self, format: DebuggerFormat) -> String {
+        let mut script = String::new();
+
+        for patch in &self.patches {
+            let command = match format {
+                DebuggerFormat::X64dbg => format!("mov byte:[{:#X}], {:#04X}", patch.target_address, patch.new),
+                DebuggerFormat::WinDbg => format!("eb {:#X} {:#04X}", patch.target_address, patch.new),
+                DebuggerFormat::Gdb => format!("set {{char}}{:#X} = {:#04X}", patch.target_address, patch.new),
+            };
+            script.push_str(&command);
+            script.push('\n');
+        }
+        script
+    }
+
+    /// This generates a Rust snippet building the same [F1337Patch], to bake a known patch into a binary.
+    /// 
+    /// The snippet declares a mutable ``var_name`` variable with [F1337Patch::new], then adds each patch with [HexPatch::new].
+    /// It expects [F1337Patch] and [HexPatch] to be in scope.
+    /// 
+    /// # Arguments
+    /// - ``var_name``: The name of the variable declared by the snippet.
+    /// 
+    /// # Returns
+    /// - The Rust statements, one per line.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(
+    ///     f1337patch.to_rust_code("patch"),
+    ///     "let mut patch = F1337Patch::new(\"test.exe\".to_string());\npatch.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));\n"
+    /// );
+    /// ```
+    pub fn to_rust_code(&self, var_name: &str) -> String {
+        // Debug formatting of a str escapes it as a Rust string literal.
+        let mut code = format!("let mut {} = F1337Patch::new({:?}.to_string());\n", var_name, self.target_filename);
+
+        for patch in &self.patches {
+            code.push_str(&format!(
+                "{}.add_patch(HexPatch::new({:#X}, {:#04X}, {:#04X}));\n",
+                var_name, patch.target_address, patch.old, patch.new
+            ));
+        }
+        code
+    }
+
+    /// This checks that ``filename`` names the target file of the patch.
+    /// 
+    /// Only the last component of ``filename`` is compared to [F1337Patch::target_filename],
+    /// so the path of the target file can be given as is.
+    /// 
+    /// # Arguments
+    /// - ``filename``: The name or path of the file to check.
+    /// 
+    /// # Returns
+    /// - ``true`` if the file name matches the target file name.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// assert!(f1337patch.matches_target("game/test.exe"));
+    /// assert!(!f1337patch.matches_target("other.exe"));
+    /// ```
+    pub fn matches_target(&self, filename: &str) -> bool {
+        std::path::Path::new(filename).file_name()
+            .is_some_and(|name| name == self.target_filename.as_str())
+    }
+
+    /// This checks that every patched byte fits in a target of ``len`` bytes.
+    /// 
+    /// # Arguments
+    /// - ``len``: The length of the target, in bytes.
+    /// 
+    /// # Returns
+    /// - ``true`` if every address is below ``len``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000000001, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.fits_in(2));
+    /// assert!(!f1337patch.fits_in(1));
+    /// ```
+    pub fn fits_in(&self, len: u64) -> bool {
+        self.patches.iter().all(|patch| patch.target_address < len)
+    }
+
+    /// This computes the minimal size a target file must have for every patch to be in bounds,
+    /// e.g. to create a new file to patch into.
+    /// 
+    /// Each patch writes a single byte, so this is the highest address plus one.
+    /// It saturates at [u64::MAX] for a patch at the highest address.
+    /// 
+    /// # Returns
+    /// - The minimal file size in bytes, or ``0`` if there are no patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x00000000000001FF, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.required_file_size(), 0x200);
+    /// ```
+    pub fn required_file_size(&self) -> u64 {
+        self.patches.iter()
+            .map(|patch| patch.target_address.saturating_add(1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// This sorts the patches by address.
+    /// 
+    /// The sort is stable: patches at the same address keep their relative order, so applying them gives the same result.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.sort_by_address();
+    /// assert_eq!(f1337patch.patches[0].target_address, 0x0000000000AF0200);
+    /// ```
+    pub fn sort_by_address(&mut self) {
+        self.patches.sort_by_key(|patch| patch.target_address);
+    }
+
+    /// This iterates over the patches by address, without reordering them unlike [F1337Patch::sort_by_address].
+    /// 
+    /// The order is stable: patches at the same address are yielded in their relative order.
+    /// 
+    /// # Returns
+    /// - An iterator over the patches, in ascending address order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.iter_sorted().next().unwrap().target_address, 0x0000000000AF0200);
+    /// assert_eq!(f1337patch.patches[0].target_address, 0x0000000000AF0206);
+    /// ```
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &HexPatch> {
+        self.sorted_indices().into_iter().map(move |index| &self.patches[index])
+    }
+
+    /// This finds the patches next to a suspiciously large gap in the patched addresses,
+    /// which usually reveals a copy-paste mistake where an address lost or gained digits.
+    /// 
+    /// The addresses are considered in ascending order, and each gap from the previous address exceeding ``threshold`` flags one patch:
+    /// the patch after the gap, unless the patch before it is the isolated one, i.e. it is also far from its own previous address
+    /// (or is the lowest address) while the patch after the gap is close to its next address.<br/>
+    /// So an outlier is flagged itself, whether it lies above the other addresses or below them (e.g. ``0x2`` among addresses around ``0xAF0200``).
+    /// 
+    /// # Arguments
+    /// - ``threshold``: The largest gap between two consecutive addresses considered normal.
+    /// 
+    /// # Returns
+    /// - The indices, in [F1337Patch::patches], of the flagged patches, in address order and listed once.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x74, 0xEB));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000000002, 0x00, 0x90));
+    /// 
+    /// assert_eq!(f1337patch.suspicious_gaps(0x10000), vec![2]);
+    /// ```
+    pub fn suspicious_gaps(&self, threshold: u64) -> Vec<usize> {
+        let indices = self.sorted_indices();
+        let addresses: Vec<u64> = indices.iter().map(|&index| self.patches[index].target_address).collect();
+        // Whether the gap before the n-th sorted address is too large, a missing neighbour counting as a large gap.
+        let far = |n: usize| n == 0 || n == addresses.len() || addresses[n] - addresses[n - 1] > threshold;
+        let mut flagged = Vec::new();
+
+        for n in (1..addresses.len()).filter(|&n| far(n)) {
+            let isolated = match far(n - 1) && !far(n + 1) {
+                true => indices[n - 1],
+                false => indices[n],
+            };
+
+            if flagged.last() != Some(&isolated) {
+                flagged.push(isolated);
+            }
+        }
+        flagged
+    }
+
+    /// This removes the duplicate patches, i.e. patches with the same address, old and new values as a previous patch.
+    /// 
+    /// The first occurrence of each patch is kept, and the patches keep their order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.dedup_all();
+    /// assert_eq!(f1337patch.patches.len(), 1);
+    /// ```
+    pub fn dedup_all(&mut self) {
+        let mut seen = std::collections::HashSet::new();
+
+        self.patches.retain(|patch| seen.insert((patch.target_address, patch.old, patch.new)));
+    }
+
+    /// This returns the runs of patches at contiguous addresses, after sorting them by address.
+    /// 
+    /// A run is a sequence of patches whose each address is the address of the previous patch plus one.<br/>
+    /// The patches are not reordered: the ranges index the patches in address order, i.e. the order of [F1337Patch::patches]
+    /// after [F1337Patch::sort_by_address], which keeps patches at the same address in the same order.
+    /// This lets a UI collapse a run into a single row, such as "patch bytes 0x100-0x104".
+    /// 
+    /// # Returns
+    /// - The index ranges of the runs in the patches sorted by address, in order. A patch alone is a run of one.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0201, 0x37, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x74, 0xEB));
+    /// 
+    /// assert_eq!(f1337patch.runs(), vec![0..2, 2..3]);
+    /// ```
+    pub fn runs(&self) -> Vec<std::ops::Range<usize>> {
+        let addresses: Vec<u64> = self.sorted_indices()
+            .into_iter()
+            .map(|index| self.patches[index].target_address)
+            .collect();
+        let mut runs = Vec::new();
+        let mut start = 0;
+
+        for index in 1..=addresses.len() {
+            let contiguous = index < addresses.len() && addresses[index - 1].checked_add(1) == Some(addresses[index]);
+
+            if !contiguous {
+                runs.push(start..index);
+                start = index;
+            }
+        }
+        runs
+    }
+
+    /// This function returns the indices of the patches sorted by address, without reordering the patches.
+    /// The sort is stable: patches at the same address keep their order, like with [F1337Patch::sort_by_address].
+    fn sorted_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.patches.len()).collect();
+
+        indices.sort_by_key(|&index| self.patches[index].target_address);
+        indices
+    }
+
+    /// This removes the patches whose address is outside ``window``.
+    /// 
+    /// Use it for sandboxed patching, where only a section of the target, such as the code section, may be modified.<br/>
+    /// The remaining patches keep their order. See [F1337Patch::reject_outside_window] to fail instead.
+    /// 
+    /// # Arguments
+    /// - ``window``: The permitted address range, end excluded.
+    /// 
+    /// # Returns
+    /// - The number of removed patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000003000, 0x37, 0x13));
+    /// 
+    /// assert_eq!(f1337patch.restrict_to_window(0x1000..0x2000), 1);
+    /// assert_eq!(f1337patch.patches.len(), 1);
+    /// ```
+    pub fn restrict_to_window(&mut self, window: std::ops::Range<u64>) -> usize {
+        let len = self.patches.len();
+
+        self.patches.retain(|patch| window.contains(&patch.target_address));
+        len - self.patches.len()
+    }
+
+    /// This checks that every patch address is inside ``window``.
+    /// 
+    /// This enforces a security boundary on what a patch may touch. See [F1337Patch::restrict_to_window] to remove the patches instead.
+    /// 
+    /// # Arguments
+    /// - ``window``: The permitted address range, end excluded.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::OutsideWindow] if a patch is outside ``window``. Contains the address of the first such patch, in patch order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, PatchFileError};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000003000, 0x37, 0x13));
+    /// 
+    /// assert_eq!(f1337patch.reject_outside_window(0x1000..0x2000), Err(PatchFileError::OutsideWindow(0x3000)));
+    /// ```
+    pub fn reject_outside_window(&self, window: std::ops::Range<u64>) -> Result<(), PatchFileError> {
+        match self.patches.iter().find(|patch| !window.contains(&patch.target_address)) {
+            Some(patch) => Err(PatchFileError::OutsideWindow(patch.target_address)),
+            None => Ok(()),
+        }
+    }
+
+    /// This counts the patches per new value, most frequent first.
+    /// 
+    /// This surfaces the most common byte written by a patch, often [X86_NOP] or ``0xEB``.<br/>
+    /// New values with the same count are sorted by value.
+    /// 
+    /// # Returns
+    /// - The ``(new_value, count)`` pairs, sorted by descending count.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, X86_NOP};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.nop_range(0x0000000000AF0200, &[0x13, 0x37]).unwrap();
+    /// 
+    /// assert_eq!(f1337patch.by_new_value_frequency(), vec![(X86_NOP, 2)]);
+    /// ```
+    pub fn by_new_value_frequency(&self) -> Vec<(u8, usize)> {
+        let mut counts = BTreeMap::new();
+
+        for patch in &self.patches {
+            *counts.entry(patch.new).or_insert(0) += 1;
+        }
+
+        let mut frequencies: Vec<(u8, usize)> = counts.into_iter().collect();
+        frequencies.sort_by(|(_, count_a), (_, count_b)| count_b.cmp(count_a));
+        frequencies
+    }
+
+    /// This checks that the [F1337Patch] round-trips through its serialized form.
+    /// 
+    /// The [F1337Patch] is serialized with [ToString::to_string] and parsed back with [F1337Patch::from_str_content]:
+    /// the target file name and patches read back must be equal to the original ones.<br/>
+    /// This catches patch sets that cannot be saved cleanly, e.g. with a target file name ending with spaces or containing a new line.
+    /// [F1337Patch::format_version] is not part of the serialized form and is not checked.
+    /// 
+    /// # Returns
+    /// - ``true`` if the [F1337Patch] serializes cleanly.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.self_check());
+    /// ```
+    pub fn self_check(&self) -> bool {
+        match Self::from_str_content(&self.to_string()) {
+            Ok(parsed) => parsed.target_filename == self.target_filename && parsed.patches == self.patches,
+            Err(_) => false,
+        }
+    }
+
+    /// This guesses the address width of the target binary from the patch addresses.
+    /// 
+    /// If the high 32 bits of every address are zero, the patch likely targets a 32-bit binary.<br/>
+    /// This helps tools pick the right address width automatically. A patch set without patches is [AddressWidth::Bits32].
+    /// 
+    /// # Returns
+    /// - [AddressWidth::Bits32] if every address is at most [u32::MAX], [AddressWidth::Bits64] otherwise.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{AddressWidth, F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.likely_address_width(), AddressWidth::Bits32);
+    /// ```
+    pub fn likely_address_width(&self) -> AddressWidth {
+        match self.patches.iter().all(|patch| patch.target_address <= u32::MAX as u64) {
+            true => AddressWidth::Bits32,
+            false => AddressWidth::Bits64,
+        }
+    }
+
+    /// This computes which bytes of a ``size`` bytes file the patches touch, e.g. to draw a heatmap of the modified areas.
+    /// 
+    /// Every [HexPatch] touches a single byte. Patches past the end of the file are ignored.
+    /// 
+    /// # Arguments
+    /// - ``size``: The size of the file, in bytes.
+    /// 
+    /// # Returns
+    /// - A vector of ``size`` booleans, ``true`` where a patch touches the byte.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.coverage_bitmap(3), vec![false, true, false]);
+    /// ```
+    pub fn coverage_bitmap(&self, size: usize) -> Vec<bool> {
+        let mut bitmap = vec![false; size];
+
+        for patch in &self.patches {
+            if let Some(covered) = usize::try_from(patch.target_address).ok().and_then(|address| bitmap.get_mut(address)) {
+                *covered = true;
+            }
+        }
+        bitmap
+    }
+
+    /// This counts the patches whose address falls inside each of ``ranges``.
+    /// 
+    /// Ranges may overlap: each range is counted independently, so a patch can be counted in several ranges.
+    /// 
+    /// # Arguments
+    /// - ``ranges``: The address ranges, end excluded.
+    /// 
+    /// # Returns
+    /// - The number of patches inside each range, in the order of ``ranges``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.count_in_ranges(&[0x0000..0x1000, 0x1000..0x2000]), vec![0, 1]);
+    /// ```
+    pub fn count_in_ranges(&self, ranges: &[std::ops::Range<u64>]) -> Vec<usize> {
+        ranges.iter()
+            .map(|range| self.patches.iter().filter(|patch| range.contains(&patch.target_address)).count())
+            .collect()
+    }
+
+    /// This checks if the [F1337Patch] and ``other`` have the same effect on a target, i.e. are functionally equivalent.
+    /// 
+    /// The resulting ``address -> new value`` maps are compared, the last patch of an address winning as when applying
+    /// the patches in order. Old values, patch order and target file names are ignored.
+    /// 
+    /// # Arguments
+    /// - ``other``: The [F1337Patch] to compare with.
+    /// 
+    /// # Returns
+    /// - ``true`` if both write the same values at the same addresses.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let mut other = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// other.add_patch(HexPatch::new(0x0000000000AF0200, 0x00, 0x37));
+    /// 
+    /// assert!(f1337patch.same_effect(&other));
+    /// ```
+    pub fn same_effect(&self, other: &F1337Patch) -> bool {
+        let effect = |f1337patch: &F1337Patch| -> BTreeMap<u64, u8> {
+            f1337patch.last_patch_per_address().into_iter().map(|(address, patch)| (address, patch.new)).collect()
+        };
+
+        effect(self) == effect(other)
+    }
+
+    /// This computes the delta patch moving a target already patched with the [F1337Patch] to the state of ``newer``,
+    /// e.g. to distribute an update of a patch.
+    /// 
+    /// The last patch of an address wins, as when applying the patches in order. For each address where the results differ:
+    /// - Patched by both: the delta patches the [F1337Patch]'s new value into ``newer``'s.
+    /// - Only patched by the [F1337Patch]: the delta restores the original old value.
+    /// - Only patched by ``newer``: the delta patches the original old value into ``newer``'s new value.
+    /// 
+    /// # Arguments
+    /// - ``newer``: The newer version of the [F1337Patch].
+    /// 
+    /// # Returns
+    /// - A new [F1337Patch] with ``newer``'s target file name and format version, and the delta patches sorted by address.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let mut newer = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// newer.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x90));
+    /// 
+    /// assert_eq!(f1337patch.delta(&newer).patches, vec![HexPatch::new(0x0000000000AF0200, 0x37, 0x90)]);
+    /// ```
+    pub fn delta(&self, newer: &F1337Patch) -> F1337Patch {
+        let current = self.last_patch_per_address();
+        let target = newer.last_patch_per_address();
+        let mut f1337patch = F1337Patch::new(newer.target_filename.clone());
+
+        f1337patch.format_version = newer.format_version;
+        for address in current.keys().chain(target.keys()).collect::<BTreeSet<_>>() {
+            // Every patch of an address checks the same old value, the original byte of the target.
+            let (old, new) = match (current.get(address), target.get(address)) {
+                (Some(current), Some(target)) => (current.new, target.new),
+                (Some(current), None) => (current.new, current.old),
+                (None, Some(target)) => (target.old, target.new),
+                (None, None) => unreachable!(),
+            };
+            if old != new {
+                f1337patch.add_patch(HexPatch::new(*address, old, new));
+            }
+        }
+        f1337patch
+    }
+
+    /// This computes the length, in bytes, of each run of contiguous patched addresses, e.g. to size write buffers.
+    /// 
+    /// The addresses are sorted and deduplicated first, so the runs are the ones found by [F1337Patch::runs]
+    /// after [F1337Patch::sort_by_address], and the write operations of [F1337Patch::to_sparse_ops].
+    /// 
+    /// # Returns
+    /// - The number of bytes of each run, in address order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0201, 0x37, 0x13));
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.run_lengths(), vec![2]);
+    /// ```
+    pub fn run_lengths(&self) -> Vec<usize> {
+        let addresses: std::collections::BTreeSet<u64> = self.patches.iter().map(|patch| patch.target_address).collect();
+        let mut lengths: Vec<usize> = Vec::new();
+        let mut previous: Option<u64> = None;
+
+        for address in addresses {
+            match (previous.and_then(|previous| previous.checked_add(1)), lengths.last_mut()) {
+                (Some(next), Some(length)) if next == address => *length += 1,
+                _ => lengths.push(1),
+            }
+            previous = Some(address);
+        }
+        lengths
+    }
+
+    /// This computes a SHA-256 identifier of the patch content.
+    /// 
+    /// The hash is computed over the canonical serialized form of the patch (see [F1337Patch]'s [std::fmt::Display] implementation),
+    /// so two patches parsed from differently formatted files (whitespace, line endings, hex case) share the same identifier.
+    /// 
+    /// Only available with the ``sha2`` feature.
+    /// 
+    /// # Returns
+    /// - The 32 bytes SHA-256 digest.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// println!("Content id : {:02x?}", f1337patch.content_id());
+    /// ```
+    #[cfg(feature = "sha2")]
+    pub fn content_id(&self) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+
+        Sha256::digest(self.to_string().as_bytes()).into()
+    }
+
+    /// This saves the [F1337Patch] to the file at ``path``, in the patch file format.
+    /// 
+    /// The file is created, or truncated if it exists, and the [F1337Patch] is written as with [ToString::to_string].
+    /// 
+    /// # Arguments
+    /// - ``path``: The path of the patch file to write.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the file cannot be written.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.save_to_file("test.1337").unwrap();
+    /// ```
+    pub fn save_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), PatchFileError> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// This canonicalizes the patch file at ``path`` in place, like ``rustfmt`` for patch files.
+    /// 
+    /// The file is read leniently (see [ParseOptions::lenient] and [ParseOptions::normalize_eol]), the patches are sorted
+    /// and deduplicated according to ``options``, and the file is written back in the patch file format.<br/>
+    /// The canonical form is written to a temporary file next to ``path``, then renamed over it,
+    /// so the file is never left half written. Comment lines are not kept.
+    /// 
+    /// # Arguments
+    /// - ``path``: The path of the patch file to format.
+    /// - ``options``: The [FormatOptions] to apply.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader_with_options]. The file is left unchanged.
+    /// - [PatchFileError::ReadError] if the file cannot be written.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, FormatOptions};
+    /// 
+    /// F1337Patch::format_file("test.1337", FormatOptions::default()).unwrap();
+    /// ```
+    pub fn format_file<P: AsRef<std::path::Path>>(path: P, options: FormatOptions) -> Result<(), PatchFileError> {
+        let path = path.as_ref();
+        let parse_options = ParseOptions {
+            lenient: true,
+            normalize_eol: true,
+            ..ParseOptions::default()
+        };
+        let mut f1337patch = Self::from_bufreader_with_options(&mut io::BufReader::new(File::open(path)?), &parse_options)?;
+
+        if options.sort {
+            f1337patch.sort_by_address();
+        }
+        if options.dedup {
+            f1337patch.dedup_all();
+        }
+
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(path.file_name().unwrap_or_default());
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+
+        std::fs::write(&temp_path, f1337patch.to_string_case(options.case))?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// This saves the reverse [F1337Patch] to the file at ``path``, to generate an undo patch file in one call.
+    /// 
+    /// This function is a wrapper for [F1337Patch::reversed] and [F1337Patch::save_to_file].
+    /// 
+    /// # Arguments
+    /// - ``path``: The path of the undo patch file to write.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::save_to_file].
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.write_reverse_to_file("test.undo.1337").unwrap();
+    /// ```
+    pub fn write_reverse_to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), PatchFileError> {
+        self.reversed().save_to_file(path)
+    }
+
+    /// This creates a new [F1337Patch] from a [File].
+    /// 
+    /// It takes a mutable reference to a [File] and returns a [Result] of [F1337Patch] or [PatchFileError].
+    /// 
+    /// This function is a wrapper for [F1337Patch::from_bufreader].
+    /// 
+    /// # Arguments
+    /// - ``patchfile``: A mutable reference to a [File].
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - [PatchFileError::ReadError] if the file can't be read. Contains [std::io::Error].
+    /// - [PatchFileError::WrongFormat] if the file is not in the right format.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::F1337Patch;
+    /// use std::fs::File;
+    /// 
+    /// let mut patchfile = File::open("test.txt").unwrap();
+    /// 
+    /// let patch = F1337Patch::from_patchfile(&mut patchfile).unwrap();
+    /// ```
+    pub fn from_patchfile(patchfile: &File) -> Result<F1337Patch, PatchFileError> {
+        Self::from_bufreader(&mut io::BufReader::new(patchfile))
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader].
+    /// 
+    /// It takes a mutable reference to a [File] and returns a [Result] of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - [PatchFileError::ReadError] if the file can't be read. Contains [std::io::Error].
+    /// - [PatchFileError::InvalidHeader] if the first line is not a header line starting with `>`. Quotes the start of the line.
+    /// - [PatchFileError::WrongFormat] if the file is not in the right format.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::F1337Patch;
+    /// use std::fs::File;
+    /// 
+    /// let mut patchfile = File::open("test.txt").unwrap();
+    /// let patch = F1337Patch::from_patchfile(&patchfile).unwrap();
+    /// ```
+    /// 
+    /// # Note
+    /// See [F1337Patch] for more information about the file format.
+    pub fn from_bufreader<R: SeekableBufRead>(bufreader: &mut R) -> Result<F1337Patch, PatchFileError> {
+        Self::from_bufreader_with_options(bufreader, &ParseOptions::default())
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], parsed according to the given [ParseOptions].
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// - ``options``: A reference to the [ParseOptions] to use.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - [PatchFileError::ReadError] if the file can't be read. Contains [std::io::Error].
+    /// - [PatchFileError::WrongFormat] if the file is not in the right format.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, ParseOptions};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(">test.exe\n0000000000AF0200 : 13 -> 37\n");
+    /// let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+    /// let patch = F1337Patch::from_bufreader_with_options(&mut content, &options).unwrap();
+    /// ```
+    pub fn from_bufreader_with_options<R: SeekableBufRead>(bufreader: &mut R, options: &ParseOptions) -> Result<F1337Patch, PatchFileError> {
+        Self::parse_bufreader(bufreader, options, &mut Vec::new())
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], along with the problems tolerated while parsing it.
+    /// 
+    /// The file is parsed like [F1337Patch::from_bufreader]. A ``@vN`` marker naming an unknown version does not fail the parsing:
+    /// the file is parsed with version 1 semantics and a [ParseWarning::UnknownVersion] is returned,
+    /// so that older readers keep working against newer files where possible.
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of a tuple of the [F1337Patch] and of the [ParseWarning]s, in file order, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, ParseWarning};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(">test.exe\n@v99\n0000000000AF0200:13->37\n");
+    /// let (f1337patch, warnings) = F1337Patch::from_bufreader_with_warnings(&mut content).unwrap();
+    /// 
+    /// assert_eq!(f1337patch.format_version(), 1);
+    /// assert_eq!(warnings, vec![ParseWarning::UnknownVersion(99)]);
+    /// ```
+    pub fn from_bufreader_with_warnings<R: SeekableBufRead>(bufreader: &mut R) -> Result<(F1337Patch, Vec<ParseWarning>), PatchFileError> {
+        let mut warnings = Vec::new();
+        let f1337patch = Self::parse_bufreader(bufreader, &ParseOptions::default(), &mut warnings)?;
+
+        Ok((f1337patch, warnings))
+    }
+
+    /// This function parses a patch file according to the given [ParseOptions], pushing the problems tolerated to ``warnings``.
+    fn parse_bufreader<R: SeekableBufRead>(bufreader: &mut R, options: &ParseOptions, warnings: &mut Vec<ParseWarning>) -> Result<F1337Patch, PatchFileError> {
+        let mut f1337patch: F1337Patch;
+        let mut first_line = String::new();
+        
+        bufreader.seek(io::SeekFrom::Start(0))?;
+        bufreader.read_line(&mut first_line)?;
+        if options.strip_bom && first_line.starts_with(BOM) {
+            first_line.drain(..BOM.len_utf8());
+        }
+        if options.allow_missing_header && !first_line.starts_with('>') {
+            f1337patch = F1337Patch::new(String::new());
+            f1337patch.push_patch_line(first_line.trim_end_matches(['\r', '\n']), options)?;
+            f1337patch.push_patch_lines(bufreader, options)?;
+            return Ok(f1337patch);
+        }
+
+        f1337patch = F1337Patch::new(Self::get_filename(first_line)?);
+        f1337patch.push_body_lines(bufreader, options, warnings)?;
+        Ok(f1337patch)
+    }
+
+    /// This function parses and adds every line of ``reader`` following the header line, including the version marker line.
+    fn push_body_lines<R: BufRead>(&mut self, reader: &mut R, options: &ParseOptions, warnings: &mut Vec<ParseWarning>) -> Result<(), PatchFileError> {
+        let mut marker = VersionMarker::new();
+
+        Self::for_each_line(reader, |line| match marker.take(line)? {
+            true => Ok(()),
+            false => self.push_patch_line(line, options),
+        })?;
+        self.format_version = marker.version();
+        warnings.append(&mut marker.warnings);
+        Ok(())
+    }
+
+    /// This function parses a ``@vN`` version marker line, returning [None] if ``line`` is not one.
+    /// Unknown versions are parsed as version 1, and reported in ``warnings``.
+    fn parse_version_marker(line: &str, warnings: &mut Vec<ParseWarning>) -> Result<Option<u32>, PatchFileError> {
+        let version = match line.strip_prefix("@v") {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        // Check the digits first: u32::from_str_radix accepts a leading '+'.
+        if version.is_empty() || !version.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(PatchFileError::WrongFormat);
+        }
+
+        let version = version.parse::<u32>()?;
+        if version == 0 || version > LATEST_FORMAT_VERSION {
+            warnings.push(ParseWarning::UnknownVersion(version));
+            return Ok(Some(1));
+        }
+        Ok(Some(version))
+    }
+
+    /// This creates a new [F1337Patch] from a [BufRead] whose header line may follow some banner lines.
+    /// 
+    /// Some tools prepend a banner before the ``>filename`` header when exporting patch files.<br/>
+    /// This function scans up to ``max_scan`` lines for the first line starting with ``>``, ignoring the lines before it,
+    /// then parses the patches after it like [F1337Patch::from_bufreader]. The reader does not need to implement [Seek].
+    /// 
+    /// # Arguments
+    /// - ``reader``: A mutable reference to any [BufRead].
+    /// - ``max_scan``: The maximum number of lines to scan for the header line, including the header line itself.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// - [PatchFileError::InvalidHeader] if none of the first ``max_scan`` lines starts with ``>``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let mut content = "Exported by x64dbg\n>test.exe\n0000000000AF0200:13->37\n".as_bytes();
+    /// let f1337patch = F1337Patch::from_bufreader_find_header(&mut content, 4).unwrap();
+    /// 
+    /// assert_eq!(f1337patch.target_filename, "test.exe");
+    /// ```
+    pub fn from_bufreader_find_header<R: BufRead>(reader: &mut R, max_scan: usize) -> Result<F1337Patch, PatchFileError> {
+        let mut line = String::new();
+
+        for _ in 0..max_scan {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            if line.starts_with('>') {
+                let mut f1337patch = F1337Patch::new(Self::get_filename(line)?);
+
+                f1337patch.push_body_lines(reader, &ParseOptions::default(), &mut Vec::new())?;
+                return Ok(f1337patch);
+            }
+        }
+        Err(PatchFileError::InvalidHeader(format!("no line starting with '>' in the first {} lines", max_scan)))
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], reading the target file name as raw bytes.
+    /// 
+    /// Unlike [F1337Patch::from_bufreader], the header line does not need to be valid UTF-8,
+    /// which allows patching files whose names are not valid UTF-8.<br/>
+    /// The raw file name is returned along the [F1337Patch], whose [target file name](F1337Patch::target_filename)
+    /// is a lossy UTF-8 conversion of it (invalid sequences are replaced by ``U+FFFD``).
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of a tuple of the [F1337Patch] and the raw target file name, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(b">caf\xE9.exe\n0000000000AF0200:13->37\n");
+    /// let (patch, filename) = F1337Patch::from_bufreader_os(&mut content).unwrap();
+    /// 
+    /// assert_eq!(filename, b"caf\xE9.exe");
+    /// ```
+    /// 
+    /// On Unix, the raw file name can be turned into a path with ``std::os::unix::ffi::OsStringExt::from_vec``.
+    pub fn from_bufreader_os<R: SeekableBufRead>(bufreader: &mut R) -> Result<(F1337Patch, Vec<u8>), PatchFileError> {
+        let options = ParseOptions::default();
+        let mut first_line = Vec::new();
+
+        bufreader.seek(io::SeekFrom::Start(0))?;
+        bufreader.read_until(b'\n', &mut first_line)?;
+
+        let filename = match first_line.strip_prefix(b">") {
+            Some(filename) => filename.trim_ascii_end().to_vec(),
+            None => return Err(Self::missing_header_error(&String::from_utf8_lossy(&first_line))),
+        };
+        let mut f1337patch = F1337Patch::new(String::from_utf8_lossy(&filename).into_owned());
+
+        f1337patch.push_body_lines(bufreader, &options, &mut Vec::new())?;
+        Ok((f1337patch, filename))
+    }
+
+    /// This creates a new [F1337Patch] from a target file name and the patch lines.
+    /// 
+    /// Each line is validated and parsed like the patch lines of [F1337Patch::from_bufreader], comment lines are skipped.<br/>
+    /// This decouples where the lines come from from their parsing, e.g. to preprocess lines before building the [F1337Patch].
+    /// 
+    /// # Arguments
+    /// - ``filename``: The target file name.
+    /// - ``lines``: The patch lines, without the header line nor line endings.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConvertionError] if a line contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - [PatchFileError::WrongFormat] if a line is not in the right format.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let lines = vec!["0000000000AF0200:13->37".to_string(), "0000000000AF0206:37->37".to_string()];
+    /// let patch = F1337Patch::from_lines("test.exe".to_string(), lines).unwrap();
+    /// ```
+    pub fn from_lines<I: IntoIterator<Item = String>>(filename: String, lines: I) -> Result<F1337Patch, PatchFileError> {
+        let options = ParseOptions::default();
+        let mut f1337patch = F1337Patch::new(filename);
+
+        for line in lines {
+            f1337patch.push_patch_line(&line, &options)?;
+        }
+        Ok(f1337patch)
+    }
+
+    /// This function parses and adds every remaining line of ``reader`` according to the given [ParseOptions].
+    fn push_patch_lines<R: BufRead>(&mut self, reader: &mut R, options: &ParseOptions) -> Result<(), PatchFileError> {
+        Self::for_each_line(reader, |line| self.push_patch_line(line, options))
+    }
+
+    /// This function calls ``on_line`` with every remaining line of ``reader``, without its line ending.
+    fn for_each_line<R: BufRead, F: FnMut(&str) -> Result<(), PatchFileError>>(reader: &mut R, mut on_line: F) -> Result<(), PatchFileError> {
+        // Reuse a single line buffer instead of allocating a String per line with BufRead::lines.
+        let mut line = String::new();
+
+        while reader.read_line(&mut line)? != 0 {
+            let content = match line.strip_suffix('\n') {
+                Some(content) => content.strip_suffix('\r').unwrap_or(content),
+                None => &line,
+            };
+            on_line(content)?;
+            line.clear();
+        }
+        Ok(())
+    }
+
+    /// This function parses a patch line according to the given [ParseOptions] and adds it, unless the line is a comment.
+    fn push_patch_line(&mut self, line: &str, options: &ParseOptions) -> Result<(), PatchFileError> {
+        let line = match options.normalize_eol {
+            true => line.trim_end_matches('\r'),
+            false => line,
+        };
+        let line = match options.strip_bom {
+            true => line.strip_prefix(BOM).unwrap_or(line),
+            false => line,
+        };
+
+        if options.is_comment(line) {
+            return Ok(());
+        }
+        Self::for_each_line_patch(line, options, |patch| {
+            if let Some(max_patches) = options.max_patches {
+                if self.patches.len() >= max_patches {
+                    return Err(PatchFileError::TooManyPatches(max_patches));
+                }
+            }
+            self.patches.push(patch);
+            Ok(())
+        })
+    }
+
+    /// This function parses a patch line according to the given [ParseOptions] and calls ``on_patch`` with each of its patches:
+    /// the patch of the line, or one patch per byte of a fill line (see [FillPatch]).
+    fn for_each_line_patch<F: FnMut(HexPatch) -> Result<(), PatchFileError>>(line: &str, options: &ParseOptions, mut on_patch: F) -> Result<(), PatchFileError> {
+        if !line.contains('*') {
+            return on_patch(Self::parse_patch_line(line, options)?);
+        }
+
+        // The bytes are expanded one at a time, so that a large count is not allocated at once.
+        let fill = FillPatch::from_line(line)?;
+        let old = fill.old.ok_or(PatchFileError::WrongFormat)?;
+        (0..fill.count as u64).try_for_each(|offset| on_patch(HexPatch::new(fill.address + offset, old, fill.new)))
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], accepting any mix of line endings.
+    /// 
+    /// This function is a wrapper for [F1337Patch::from_bufreader_with_options] with [ParseOptions::normalize_eol] enabled.
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(">test.exe\r\r\n0000000000AF0200:13->37\r\r\n0000000000AF0206:37->37\n");
+    /// let patch = F1337Patch::from_bufreader_normalize_eol(&mut content).unwrap();
+    /// ```
+    pub fn from_bufreader_normalize_eol<R: SeekableBufRead>(bufreader: &mut R) -> Result<F1337Patch, PatchFileError> {
+        let options = ParseOptions {
+            normalize_eol: true,
+            ..ParseOptions::default()
+        };
+
+        Self::from_bufreader_with_options(bufreader, &options)
+    }
+
+    /// This creates a new [F1337Patch] from the entry named ``entry`` of the zip archive at ``archive``.
+    /// 
+    /// The entry is read into memory, then parsed with [F1337Patch::from_bufreader].
+    /// 
+    /// # Arguments
+    /// - ``archive``: The path of the zip archive.
+    /// - ``entry``: The name of the patch file entry in the archive.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// - [PatchFileError::ReadError] if the archive cannot be opened or read, or if it does not contain ``entry``.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let f1337patch = F1337Patch::from_zip_entry("patches.zip", "test.1337").unwrap();
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn from_zip_entry<P: AsRef<std::path::Path>>(archive: P, entry: &str) -> Result<F1337Patch, PatchFileError> {
+        let mut archive = zip::ZipArchive::new(File::open(archive)?).map_err(io::Error::from)?;
+        let mut content = Vec::new();
+
+        archive.by_name(entry).map_err(io::Error::from)?.read_to_end(&mut content)?;
+        Self::from_bufreader(&mut io::Cursor::new(content))
+    }
+
+    /// This checks that the content of a patch file parses the same with the strict and the [lenient](ParseOptions::lenient) parsers.
+    /// 
+    /// Patch file authors can use it to make sure their file does not rely on the lenient parser's tolerance,
+    /// e.g. a trailing space after a patch line.
+    /// 
+    /// # Arguments
+    /// - ``content``: The content of the patch file.
+    /// 
+    /// # Returns
+    /// - ``true`` if both parsers succeed and produce the same [F1337Patch], ``false`` otherwise.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// assert!(F1337Patch::is_strictly_valid(">test.exe\n0000000000AF0200:13->37\n"));
+    /// assert!(!F1337Patch::is_strictly_valid(">test.exe\n0000000000AF0200 : 13 -> 37\n"));
+    /// ```
+    pub fn is_strictly_valid(content: &str) -> bool {
+        let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+        let lenient = Self::from_bufreader_with_options(&mut io::Cursor::new(content), &options);
+
+        match (Self::from_str_content(content), lenient) {
+            (Ok(strict), Ok(lenient)) => strict == lenient,
+            _ => false,
+        }
+    }
+
+    /// This creates a new [F1337Patch] from the content of a patch file.
+    /// 
+    /// This function is a wrapper for [F1337Patch::from_bufreader].
+    /// 
+    /// # Arguments
+    /// - ``content``: The content of the patch file.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let f1337patch = F1337Patch::from_str_content(">test.exe\n0000000000AF0200:13->37\n").unwrap();
+    /// 
+    /// assert_eq!(f1337patch.patches.len(), 1);
+    /// ```
+    pub fn from_str_content(content: &str) -> Result<F1337Patch, PatchFileError> {
+        Self::from_bufreader(&mut io::Cursor::new(content))
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], along with the position of each patch line in the patch file.
+    /// 
+    /// This lets editors map the patches back to their source lines, e.g. to jump to the line of a patch.
+    /// 
+    /// # Arguments
+    /// - ``reader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of a tuple of the [F1337Patch] and of the ``(line_number, byte_offset)`` span of each patch, or [PatchFileError].
+    ///   Line numbers start at 1 with the header line, and byte offsets are the offset of the start of the line in the file.
+    ///   The n-th span is the span of the n-th patch.
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(">test.exe\n0000000000AF0200:13->37\n");
+    /// let (f1337patch, spans) = F1337Patch::from_bufreader_with_spans(&mut content).unwrap();
+    /// 
+    /// assert_eq!(spans, vec![(2, 10)]);
+    /// ```
+    pub fn from_bufreader_with_spans<R: SeekableBufRead>(reader: &mut R) -> Result<(F1337Patch, Vec<(usize, usize)>), PatchFileError> {
+        let options = ParseOptions::default();
+        let mut spans = Vec::new();
+        let mut line = String::new();
+
+        reader.seek(io::SeekFrom::Start(0))?;
+        let mut offset = reader.read_line(&mut line)?;
+        let mut f1337patch = F1337Patch::new(Self::get_filename(std::mem::take(&mut line))?);
+        let mut marker = VersionMarker::new();
+
+        for line_number in 2.. {
+            let len = reader.read_line(&mut line)?;
+            if len == 0 {
+                break;
+            }
+
+            let content = match line.strip_suffix('\n') {
+                Some(content) => content.strip_suffix('\r').unwrap_or(content),
+                None => &line,
+            };
+            if !marker.take(content)? {
+                let patches = f1337patch.patches.len();
+                f1337patch.push_patch_line(content, &options)?;
+                if f1337patch.patches.len() > patches {
+                    spans.push((line_number, offset));
+                }
+            }
+            offset += len;
+            line.clear();
+        }
+
+        f1337patch.format_version = marker.version();
+        Ok((f1337patch, spans))
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], replacing invalid UTF-8 sequences instead of failing.
+    /// 
+    /// Lines are read as bytes and decoded with [String::from_utf8_lossy]: invalid sequences become ``U+FFFD``.<br/>
+    /// This is useful for files with stray bytes in comments or in the target file name. Patch lines are still strictly checked.
+    /// 
+    /// # Arguments
+    /// - ``reader``: A mutable reference to a any BufReader that implements Seek.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader], except for invalid UTF-8.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(b">test.exe\n# Caf\xE9\n0000000000AF0200:13->37\n".to_vec());
+    /// let f1337patch = F1337Patch::from_bufreader_lossy_utf8(&mut content).unwrap();
+    /// 
+    /// assert_eq!(f1337patch.patches.len(), 1);
+    /// ```
+    pub fn from_bufreader_lossy_utf8<R: SeekableBufRead>(reader: &mut R) -> Result<F1337Patch, PatchFileError> {
+        let options = ParseOptions::default();
+        let mut f1337patch: Option<F1337Patch> = None;
+        let mut marker = VersionMarker::new();
+        let mut line = Vec::new();
+
+        reader.seek(io::SeekFrom::Start(0))?;
+        while reader.read_until(b'\n', &mut line)? != 0 {
+            let decoded = String::from_utf8_lossy(&line);
+
+            match f1337patch.as_mut() {
+                None => f1337patch = Some(F1337Patch::new(Self::get_filename(decoded.into_owned())?)),
+                Some(f1337patch) => {
+                    let content = match decoded.strip_suffix('\n') {
+                        Some(content) => content.strip_suffix('\r').unwrap_or(content),
+                        None => &decoded,
+                    };
+                    if !marker.take(content)? {
+                        f1337patch.push_patch_line(content, &options)?;
+                    }
+                },
+            }
+            line.clear();
+        }
+
+        // An empty file has no header line, like with F1337Patch::from_bufreader.
+        let mut f1337patch = f1337patch.ok_or(PatchFileError::WrongFormat)?;
+        f1337patch.format_version = marker.version();
+        Ok(f1337patch)
+    }
+
+    /// This creates a new [F1337Patch] from a [std::io::BufReader], reading at most ``max_patches`` patches.
+    /// 
+    /// This function is a wrapper for [F1337Patch::from_bufreader_with_options] with [ParseOptions::max_patches] set.<br/>
+    /// Use it to parse untrusted files.
+    /// 
+    /// # Arguments
+    /// - ``bufreader``: A mutable reference to a any BufReader that implements Seek.
+    /// - ``max_patches``: The maximum number of patches allowed.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader].
+    /// - [PatchFileError::TooManyPatches] if the file contains more than ``max_patches`` patches.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut content = Cursor::new(">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n");
+    /// 
+    /// assert!(F1337Patch::from_bufreader_limited(&mut content, 1).is_err());
+    /// ```
+    pub fn from_bufreader_limited<R: SeekableBufRead>(bufreader: &mut R, max_patches: usize) -> Result<F1337Patch, PatchFileError> {
+        let options = ParseOptions {
+            max_patches: Some(max_patches),
+            ..ParseOptions::default()
+        };
+
+        Self::from_bufreader_with_options(bufreader, &options)
+    }
+
+    /// This function validates and extracts the patch of a line according to the given [ParseOptions].
+    fn parse_patch_line(line: &str, options: &ParseOptions) -> Result<HexPatch, PatchFileError> {
+        if options.lenient {
+            return Self::get_hex_patch_from_line_lenient(line);
+        }
+
+        match Self::decode_patch_line(line) {
+            Some(patch) => Ok(patch),
+            None => {
+                Self::check_address_width(line)?;
+                Err(PatchFileError::WrongFormat)
+            },
+        }
+    }
+
+    /// This function checks that the address field of a patch line, before ``:``, is not longer than 16 digits.
+    /// A longer address is reported like a number too large to fit in a [u64], whatever its leading digits are.
+    fn check_address_width(line: &str) -> Result<(), std::num::ParseIntError> {
+        let address = line.split(':').next().unwrap_or_default().trim();
+
+        match address.len() > 16 {
+            // 17 hex digits without leading zero always overflow a u64, which gives the error of a too long address.
+            true => Err(u64::from_str_radix("10000000000000000", 16).unwrap_err()),
+            false => Ok(()),
+        }
+    }
+
+    /// This function extracts patch from given line, splitting on the separators instead of using fixed offsets.
+    /// Whitespace around the address, old and new values is ignored.
+    fn get_hex_patch_from_line_lenient(line: &str) -> Result<HexPatch, PatchFileError> {
+        let (address, values) = line.split_once(':').ok_or(PatchFileError::WrongFormat)?;
+        let (old, new) = values.split_once("->").ok_or(PatchFileError::WrongFormat)?;
+        let (address, old, new) = (address.trim(), old.trim(), new.trim());
+
+        Self::check_address_width(address)?;
+        // Check the length of each field and that they are only in hex digits.
+        if address.len() != 16 || old.len() != 2 || new.len() != 2 {
+            return Err(PatchFileError::WrongFormat);
+        }
+        if ![address, old, new].iter().all(|field| field.chars().all(|c| c.is_ascii_hexdigit())) {
+            return Err(PatchFileError::WrongFormat);
+        }
+
+        Ok(HexPatch::new(
+            u64::from_str_radix(address, 16)?,
+            u8::from_str_radix(old, 16)?,
+            u8::from_str_radix(new, 16)?,
+        ))
+    }
+
+    /// This function checks that patch line is in the right format.
+    /// 
+    /// # Arguments
+    /// - ``line``: A reference to a [str].
+    /// 
+    /// # Returns
+    /// - [Result] of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::WrongFormat] if the line is not in the right format.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let line = "0000000000AF0200:13->37".to_string();
+    /// F1337Patch::check_patch_line_format(&line).unwrap();
+    /// ```
+    /// 
+    /// # Note
+    /// See [F1337Patch] for more information about the file format.
+    pub fn check_patch_line_format(line: &str) -> Result<(), PatchFileError> {
+        match Self::decode_patch_line(line) {
+            Some(_) => Ok(()),
+            None => Err(PatchFileError::WrongFormat),
+        }
+    }
+
+    /// This function validates and decodes a strict patch line in a single pass over its bytes.
+    /// 
+    /// Returns [None] if the line is not in the ``AAAAAAAAAAAAAAAA:OO->NN`` format.
+    fn decode_patch_line(line: &str) -> Option<HexPatch> {
+        let bytes = line.as_bytes();
+
+        // Check if line is 23 characters long, with ":" and "->" in the right place.
+        if bytes.len() != 23 || bytes[16] != b':' || &bytes[19..21] != b"->" {
+            return None;
+        }
+        // Decode address, old an new values, which must be only in hex digits.
+        let hex_value = |digits: &[u8]| digits.iter().try_fold(0u64, |value, &digit| {
+            Some(value << 4 | (digit as char).to_digit(16)? as u64)
+        });
+
+        Some(HexPatch::new(
+            hex_value(&bytes[0..16])?,
+            hex_value(&bytes[17..19])? as u8,
+            hex_value(&bytes[21..23])? as u8,
+        ))
+    }
+
+    /// This function reports every problem of a patch line.
+    /// 
+    /// Unlike [F1337Patch::check_patch_line_format], it does not stop at the first problem,
+    /// which gives a complete picture when fixing a malformed file by hand.
+    /// 
+    /// # Arguments
+    /// - ``line``: A reference to a [str].
+    /// 
+    /// # Returns
+    /// - A [Vec] of [LineDiagnostic], empty if the line is in the right format.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, LineDiagnostic};
+    /// 
+    /// let diagnostics = F1337Patch::diagnose_line("0000000000AF0200;13->ZZ");
+    /// assert_eq!(diagnostics, vec![LineDiagnostic::MissingColon, LineDiagnostic::NonHexNew]);
+    /// ```
+    /// 
+    /// # Note
+    /// See [F1337Patch] for more information about the file format.
+    pub fn diagnose_line(line: &str) -> Vec<LineDiagnostic> {
+        let bytes = line.as_bytes();
+        let mut diagnostics = Vec::new();
+        // Fields are truncated to what the line contains, a too short line is reported as a wrong length.
+        let field = |start: usize, end: usize| &bytes[start.min(bytes.len())..end.min(bytes.len())];
+        let is_hex = |field: &[u8]| field.iter().all(|c| c.is_ascii_hexdigit());
+
+        if bytes.len() != 23 {
+            diagnostics.push(LineDiagnostic::WrongLength(bytes.len()));
+        }
+        if bytes.get(16) != Some(&b':') {
+            diagnostics.push(LineDiagnostic::MissingColon);
+        }
+        if bytes.get(19..21) != Some(b"->".as_slice()) {
+            diagnostics.push(LineDiagnostic::MissingArrow);
+        }
+        if !is_hex(field(0, 16)) {
+            diagnostics.push(LineDiagnostic::NonHexAddress);
+        }
+        if !is_hex(field(17, 19)) {
+            diagnostics.push(LineDiagnostic::NonHexOld);
+        }
+        if !is_hex(field(21, 23)) {
+            diagnostics.push(LineDiagnostic::NonHexNew);
+        }
+        diagnostics
+    }
+
+    /// This function checks that the header line is in the right format.
+    /// 
+    /// The header must start with ``>`` and be followed by a non-empty target file name.
+    /// 
+    /// # Arguments
+    /// - ``line``: A reference to a [str].
+    /// 
+    /// # Returns
+    /// - [Result] of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidHeader] if the line is not a valid header. Contains the reason.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// F1337Patch::check_header_format(">test.exe").unwrap();
+    /// assert!(F1337Patch::check_header_format(">").is_err());
+    /// ```
+    /// 
+    /// # Note
+    /// See [F1337Patch] for more information about the file format.
+    pub fn check_header_format(line: &str) -> Result<(), PatchFileError> {
+        let filename = match line.strip_prefix('>') {
+            Some(filename) => filename,
+            None => return Err(PatchFileError::InvalidHeader("header must start with '>'".to_string())),
+        };
+        if filename.trim().is_empty() {
+            return Err(PatchFileError::InvalidHeader("header does not contain a target file name".to_string()));
+        }
+        Ok(())
+    }
+
+    /// This function extracts patch from given line.
+    /// 
+    /// # Arguments
+    /// - ``line``: A reference to a [str].
+    /// 
+    /// # Returns
+    /// - [Result] of [HexPatch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ConvertionError] if the file contains invalid hex values. Contains [std::num::ParseIntError].
+    /// - [std::num::IntErrorKind::PosOverflow] if the address field is longer than 16 digits, whatever its leading digits are.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// 
+    /// let line = "0000000000AF0200:13->37".to_string();
+    /// let patch = F1337Patch::get_hex_patch_from_line(&line).unwrap();
+    /// ```
+    pub fn get_hex_patch_from_line(line: &str) -> Result<HexPatch, std::num::ParseIntError> {
+        Self::check_address_width(line)?;
+        let address = u64::from_str_radix(&line[0..16], 16)?;
+        let old = u8::from_str_radix(&line[17..19], 16)?;
+        let new = u8::from_str_radix(&line[21..23], 16)?;
+
+        Ok(HexPatch::new(address, old, new))
+    }
+
+    /// This checks that ``target`` contains the [old value](HexPatch::old) of every patch.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of ``true`` if every old value matches, ``false`` if one does not or is past the end of ``target``, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the target can't be read or seeked. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.verify_original(&mut Cursor::new(vec![0x00, 0x13])).unwrap());
+    /// ```
+    pub fn verify_original<R: Read + Seek>(&self, target: &mut R) -> Result<bool, PatchFileError> {
+        for patch in &self.patches {
+            if Self::read_byte_at(target, patch.target_address)? != Some(patch.old) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// This checks that ``target`` contains the old value of every patch like [F1337Patch::verify_original],
+    /// then restores the position of ``target``.
+    /// 
+    /// This is useful when ``target`` is shared with other code expecting the position to be left where it was.<br/>
+    /// The position is restored even if the verification fails with an error.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - See [F1337Patch::verify_original].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the target can't be read or seeked. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::{Cursor, Seek, SeekFrom};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let mut target = Cursor::new(vec![0x00, 0x13]);
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// target.seek(SeekFrom::Start(0)).unwrap();
+    /// 
+    /// assert!(f1337patch.verify_original_preserving(&mut target).unwrap());
+    /// assert_eq!(target.position(), 0);
+    /// ```
+    pub fn verify_original_preserving<R: Read + Seek>(&self, target: &mut R) -> Result<bool, PatchFileError> {
+        let position = target.stream_position()?;
+        let verified = self.verify_original(target);
+
+        target.seek(io::SeekFrom::Start(position))?;
+        verified
+    }
+
+    /// This reads the bytes around each patch from ``target``, to show the surrounding code of each edit.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read] and [Seek], e.g. a [File].
+    /// - ``context``: The number of bytes to read before and after each target address.
+    /// 
+    /// # Returns
+    /// - Result of one [BytePreview] per patch, in patch order, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the target can't be read or seeked. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+    /// 
+    /// let previews = f1337patch.context_preview(&mut Cursor::new(vec![0x85, 0x74, 0x05]), 4).unwrap();
+    /// assert_eq!(previews[0].before, vec![0x85]);
+    /// assert_eq!(previews[0].after, vec![0x05]);
+    /// ```
+    pub fn context_preview<R: Read + Seek>(&self, target: &mut R, context: usize) -> Result<Vec<BytePreview>, PatchFileError> {
+        let mut previews = Vec::with_capacity(self.patches.len());
+
+        for patch in &self.patches {
+            let start = patch.target_address.saturating_sub(context as u64);
+            let mut before = Vec::new();
+            let mut after = Vec::new();
+
+            target.seek(io::SeekFrom::Start(start))?;
+            target.by_ref().take(patch.target_address - start).read_to_end(&mut before)?;
+            if let Some(next) = patch.target_address.checked_add(1) {
+                target.seek(io::SeekFrom::Start(next))?;
+                target.by_ref().take(context as u64).read_to_end(&mut after)?;
+            }
+            previews.push(BytePreview {
+                target_address: patch.target_address,
+                before,
+                after,
+                old: patch.old,
+                new: patch.new,
+            });
+        }
+        Ok(previews)
+    }
+
+    /// This replaces the old value of every patch with the byte currently found at its address in ``target``.
+    /// 
+    /// Use it to author patches against a specific binary, or to fix patches authored against a slightly different one.<br/>
+    /// The patches are left unchanged if any address is past the end of ``target``.
+    /// 
+    /// # Arguments
+    /// - ``target``: The binary to read the old values from.
+    /// 
+    /// # Returns
+    /// - Result of [()] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::OutOfBounds] if an address is past the end of ``target``. Contains the first such address.
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000000001, 0x00, 0x37));
+    /// 
+    /// f1337patch.resync_old_from(&mut Cursor::new(vec![0x00, 0x13])).unwrap();
+    /// assert_eq!(f1337patch.patches[0].old, 0x13);
+    /// ```
+    pub fn resync_old_from<R: Read + Seek>(&mut self, target: &mut R) -> Result<(), PatchFileError> {
+        let mut old_values = Vec::with_capacity(self.patches.len());
+
+        for patch in &self.patches {
+            match Self::read_byte_at(target, patch.target_address)? {
+                Some(old) => old_values.push(old),
+                None => return Err(PatchFileError::OutOfBounds(patch.target_address)),
+            }
+        }
+        for (patch, old) in self.patches.iter_mut().zip(old_values) {
+            patch.old = old;
+        }
+        Ok(())
+    }
+
+    /// This counts how the patches would be applied to ``target``, in a single read pass.
+    /// 
+    /// Each patch falls in exactly one category of [Preflight], checked in this order:
+    /// - out of bounds if its address is past the end of ``target``,
+    /// - will apply if ``target`` contains its old value,
+    /// - already applied if ``target`` contains its new value,
+    /// - mismatched otherwise.
+    /// 
+    /// This is the report to print before asking for confirmation to apply the patches. ``target`` is not modified.
+    /// 
+    /// # Arguments
+    /// - ``target``: The target to check.
+    /// 
+    /// # Returns
+    /// - Result of [Preflight] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let preflight = f1337patch.preflight(&mut Cursor::new(vec![0x13, 0x37])).unwrap();
+    /// assert_eq!((preflight.will_apply, preflight.already_applied), (1, 1));
+    /// ```
+    pub fn preflight<R: Read + Seek>(&self, target: &mut R) -> Result<Preflight, PatchFileError> {
+        let mut preflight = Preflight::default();
+
+        for patch in &self.patches {
+            match Self::read_byte_at(target, patch.target_address)? {
+                None => preflight.out_of_bounds += 1,
+                Some(found) if found == patch.old => preflight.will_apply += 1,
+                Some(found) if found == patch.new => preflight.already_applied += 1,
+                Some(_) => preflight.mismatched += 1,
+            }
+        }
+        Ok(preflight)
+    }
+
+    /// This checks that ``target`` is a valid target for the patches, i.e. they can be safely applied to it.
+    /// 
+    /// It combines [F1337Patch::matches_target], [F1337Patch::fits_in] and [F1337Patch::verify_original]:
+    /// ``filename`` must name the target file, and ``target`` must be long enough and contain every old value.
+    /// 
+    /// # Arguments
+    /// - ``target``: The target to check.
+    /// - ``filename``: The name or path of ``target``.
+    /// 
+    /// # Returns
+    /// - Result of ``true`` if ``target`` is a valid target, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// assert!(f1337patch.is_valid_target(&mut Cursor::new(vec![0x00, 0x13]), "test.exe").unwrap());
+    /// ```
+    pub fn is_valid_target<R: Read + Seek>(&self, target: &mut R, filename: &str) -> Result<bool, PatchFileError> {
+        if !self.matches_target(filename) || !self.fits_in(target.seek(io::SeekFrom::End(0))?) {
+            return Ok(false);
+        }
+        self.verify_original(target)
+    }
+
+    /// This re-reads ``target`` after an apply and returns the addresses which do not hold their new value.
+    /// 
+    /// When several patches target the same address, the new value of the last one is expected, as when applying the patches in order.<br/>
+    /// Addresses past the end of ``target`` do not hold their new value either. ``target`` is not modified.
+    /// 
+    /// # Arguments
+    /// - ``target``: The patched target to check.
+    /// 
+    /// # Returns
+    /// - Result of the addresses of the patches which did not take effect, sorted and without duplicates, or [PatchFileError].
+    ///   An empty vector means every patch took effect.
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let mut target = Cursor::new(vec![0x00, 0x13]);
+    /// f1337patch.apply_to(&mut target).unwrap();
+    /// assert!(f1337patch.confirm_applied(&mut target).unwrap().is_empty());
+    /// ```
+    pub fn confirm_applied<R: Read + Seek>(&self, target: &mut R) -> Result<Vec<u64>, PatchFileError> {
+        let mut not_applied = Vec::new();
+
+        for (address, patch) in self.last_patch_per_address() {
+            if Self::read_byte_at(target, address)? != Some(patch.new) {
+                not_applied.push(address);
+            }
+        }
+        Ok(not_applied)
+    }
+
+    /// This splits the patches into the ones already applied to ``target`` and the pending ones, based on its current state.
+    /// 
+    /// A patch is already applied if ``target`` holds its new value. Every other patch is pending,
+    /// including patches whose old value is not found or whose address is past the end of ``target``.<br/>
+    /// This lets an installer show and act on each group separately. ``target`` is not modified.
+    /// 
+    /// # Arguments
+    /// - ``target``: The target to check.
+    /// 
+    /// # Returns
+    /// - Result of a tuple of the already applied and pending patches, each with the same target file name and in their original order, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let (applied, pending) = f1337patch.split_by_applied(&mut Cursor::new(vec![0x37, 0x13])).unwrap();
+    /// assert_eq!((applied.patches.len(), pending.patches.len()), (1, 1));
+    /// ```
+    pub fn split_by_applied<R: Read + Seek>(&self, target: &mut R) -> Result<(F1337Patch, F1337Patch), PatchFileError> {
+        let mut applied = F1337Patch::new(self.target_filename.clone());
+        let mut pending = F1337Patch::new(self.target_filename.clone());
+
+        (applied.format_version, pending.format_version) = (self.format_version, self.format_version);
+        for patch in &self.patches {
+            match Self::read_byte_at(target, patch.target_address)? == Some(patch.new) {
+                true => applied.patches.push(*patch),
+                false => pending.patches.push(*patch),
+            }
+        }
+        Ok((applied, pending))
+    }
+
+    /// This finds which of ``candidates`` the patches most likely target, among several binaries or versions.
+    /// 
+    /// The old value of every patch is checked against each candidate, and the candidate with the most matching old values wins.
+    /// Ties go to the first candidate. A candidate which cannot be read counts the matches found before the error.
+    /// 
+    /// # Arguments
+    /// - ``candidates``: The ``(filename, target)`` candidates.
+    /// 
+    /// # Returns
+    /// - The filename of the best candidate, or [None] if no candidate matches any old value.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let mut candidates = [
+    ///     ("v1/test.exe", Cursor::new(vec![0x00, 0x00])),
+    ///     ("v2/test.exe", Cursor::new(vec![0x00, 0x13])),
+    /// ];
+    /// assert_eq!(f1337patch.best_match(&mut candidates), Some("v2/test.exe"));
+    /// ```
+    pub fn best_match<'a, R: Read + Seek>(&self, candidates: &'a mut [(&str, R)]) -> Option<&'a str> {
+        let mut best: Option<(usize, usize)> = None;
+
+        for (index, (_, target)) in candidates.iter_mut().enumerate() {
+            let matches = self.patches.iter()
+                .map_while(|patch| Self::read_byte_at(target, patch.target_address).ok())
+                .zip(&self.patches)
+                .filter(|(found, patch)| *found == Some(patch.old))
+                .count();
+
+            if matches > 0 && best.is_none_or(|(_, best_matches)| matches > best_matches) {
+                best = Some((index, matches));
+            }
+        }
+
+        let candidates: &'a [(&str, R)] = candidates;
+        best.map(|(index, _)| candidates[index].0)
+    }
+
+    /// This applies the patches to ``target``.
+    /// 
+    /// Every [old value](HexPatch::old) is verified against the target before anything is written,
+    /// so the target is left untouched if any of them does not match.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of the number of patches applied or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::OldValueMismatch] if a byte of the target is not the old value of its patch.
+    /// - [PatchFileError::OutOfBounds] if a patch address is past the end of the target.
+    /// - [PatchFileError::ReadError] if the target can't be read, written or seeked. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::F1337Patch;
+    /// use std::fs::{File, OpenOptions};
+    /// 
+    /// let patch = F1337Patch::from_patchfile(&File::open("test.txt").unwrap()).unwrap();
+    /// let mut target = OpenOptions::new().read(true).write(true).open(&patch.target_filename).unwrap();
+    /// 
+    /// patch.apply_to(&mut target).unwrap();
+    /// ```
+    pub fn apply_to<W: Read + Write + Seek>(&self, target: &mut W) -> Result<usize, PatchFileError> {
+        self.apply_to_maybe(target, false)
+    }
+
+    /// This applies the patches to ``target`` like [F1337Patch::apply_to], unless ``dry_run`` is ``true``.
+    /// 
+    /// A dry run performs the whole verification and returns the same count, but skips the writes,
+    /// which backs a ``--dry-run`` flag with the same code path as the actual apply.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// - ``dry_run``: Whether to skip the writes.
+    /// 
+    /// # Returns
+    /// - Result of the number of patches applied, or that would be applied, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::apply_to].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let mut target = Cursor::new(vec![0x00, 0x13]);
+    /// assert_eq!(f1337patch.apply_to_maybe(&mut target, true).unwrap(), 1);
+    /// assert_eq!(target.into_inner(), vec![0x00, 0x13]);
+    /// ```
+    pub fn apply_to_maybe<W: Read + Write + Seek>(&self, target: &mut W, dry_run: bool) -> Result<usize, PatchFileError> {
+        for patch in &self.patches {
+            Self::check_old_value(target, patch)?;
+        }
+        if !dry_run {
+            for patch in &self.patches {
+                Self::write_byte_at(target, patch.target_address, patch.new)?;
+            }
+        }
+
+        Ok(self.patches.len())
+    }
+
+    /// This applies the patches to ``target`` like [F1337Patch::apply_to], writing an audit trail to ``log``.
+    /// 
+    /// One line is written to ``log`` for each patch applied, in the patch line format followed by the outcome:
+    /// ```text
+    /// 0000000000AF0200:13->37 applied
+    /// ```
+    /// If the verification fails, the failing patch is logged with the reason and nothing is written to ``target``:
+    /// ```text
+    /// 0000000000AF0200:13->37 mismatch, found 00
+    /// 0000000000AF0206:37->37 out of bounds
+    /// ```
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// - ``log``: A mutable reference to anything that implements [Write].
+    /// 
+    /// # Returns
+    /// - Result of the number of patches applied or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::apply_to].
+    /// - [PatchFileError::ReadError] if the log can't be written. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let mut target = Cursor::new(vec![0x13]);
+    /// let mut log = Vec::new();
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+    /// f1337patch.apply_to_logged(&mut target, &mut log).unwrap();
+    /// 
+    /// assert_eq!(String::from_utf8(log).unwrap(), "0000000000000000:13->37 applied\n");
+    /// ```
+    pub fn apply_to_logged<W: Read + Write + Seek, L: Write>(&self, target: &mut W, log: &mut L) -> Result<usize, PatchFileError> {
+        for patch in &self.patches {
+            if let Err(error) = Self::check_old_value(target, patch) {
+                match error {
+                    PatchFileError::OldValueMismatch { found, .. } => writeln!(log, "{} mismatch, found {:02X}", patch, found)?,
+                    PatchFileError::OutOfBounds(_) => writeln!(log, "{} out of bounds", patch)?,
+                    _ => (),
+                }
+                return Err(error);
+            }
+        }
+        for patch in &self.patches {
+            Self::write_byte_at(target, patch.target_address, patch.new)?;
+            writeln!(log, "{} applied", patch)?;
+        }
+
+        Ok(self.patches.len())
+    }
+
+    /// This applies the patches to ``target`` like [F1337Patch::apply_to], restoring the written bytes if a write fails.
+    /// 
+    /// The original byte at each address is recorded before it is overwritten. If any read or write fails mid-way,
+    /// every byte already written is restored, in reverse order, before the error is returned,
+    /// so the target is never left half-patched.<br/>
+    /// Restoring is done on a best effort basis: if the target can't be written at all anymore, it can't be restored either.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of the number of patches applied or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::apply_to].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// let mut target = Cursor::new(vec![0x13]);
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+    /// f1337patch.apply_to_transactional(&mut target).unwrap();
+    /// ```
+    pub fn apply_to_transactional<W: Read + Write + Seek>(&self, target: &mut W) -> Result<usize, PatchFileError> {
+        let mut written: Vec<(u64, u8)> = Vec::new();
+
+        for patch in &self.patches {
+            Self::check_old_value(target, patch)?;
+        }
+        for patch in &self.patches {
+            let result = Self::read_byte_at(target, patch.target_address)
+                .and_then(|original| original.ok_or(PatchFileError::OutOfBounds(patch.target_address)))
+                .and_then(|original| {
+                    Self::write_byte_at(target, patch.target_address, patch.new)?;
+                    written.push((patch.target_address, original));
+                    Ok(())
+                });
+
+            if let Err(error) = result {
+                for (address, original) in written.iter().rev() {
+                    // Keep restoring the other bytes even if one of them fails.
+                    let _ = Self::write_byte_at(target, *address, *original);
+                }
+                return Err(error);
+            }
+        }
+
+        Ok(self.patches.len())
+    }
+
+    /// This applies the patches to ``target`` like [F1337Patch::apply_to], recording the original bytes in a journal file at ``journal_path``.
+    /// 
+    /// Before each byte is overwritten, its address and original value are appended to the journal, so the target can be
+    /// restored exactly later with [F1337Patch::revert_from_journal], regardless of the declared old values.<br/>
+    /// The journal starts with the ``1337JRNL`` magic bytes, followed by one entry per patch, in patch order:
+    /// the address as a little-endian [u64] and the original byte.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// - ``journal_path``: The path of the journal file to create. An existing file is overwritten.
+    /// 
+    /// # Returns
+    /// - Result of the number of applied patches or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::apply_to]. The journal is not created if the verification fails.
+    /// - [PatchFileError::ReadError] if the journal cannot be written.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// use std::fs::OpenOptions;
+    /// use std::path::Path;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// let mut target = OpenOptions::new().read(true).write(true).open("test.exe").unwrap();
+    /// f1337patch.apply_to_with_journal(&mut target, Path::new("test.exe.journal")).unwrap();
+    /// ```
+    pub fn apply_to_with_journal<W: Read + Write + Seek>(&self, target: &mut W, journal_path: &std::path::Path) -> Result<usize, PatchFileError> {
+        for patch in &self.patches {
+            Self::check_old_value(target, patch)?;
+        }
+
+        let mut journal = File::create(journal_path)?;
+        journal.write_all(JOURNAL_MAGIC)?;
+        for patch in &self.patches {
+            let original = Self::read_byte_at(target, patch.target_address)?
+                .ok_or(PatchFileError::OutOfBounds(patch.target_address))?;
+
+            // Record the original byte before overwriting it.
+            journal.write_all(&patch.target_address.to_le_bytes())?;
+            journal.write_all(&[original])?;
+            Self::write_byte_at(target, patch.target_address, patch.new)?;
+        }
+        journal.sync_all()?;
+
+        Ok(self.patches.len())
+    }
+
+    /// This restores ``target`` from the journal at ``journal_path``, written by [F1337Patch::apply_to_with_journal].
+    /// 
+    /// The entries are restored in reverse order, so the original bytes are restored exactly, even when several patches target the same address.<br/>
+    /// The whole journal is validated before anything is written to ``target``.
+    /// 
+    /// # Arguments
+    /// - ``target``: A mutable reference to anything that implements [Write] and [Seek], e.g. a [File].
+    /// - ``journal_path``: The path of the journal file.
+    /// 
+    /// # Returns
+    /// - Result of the number of reverted entries or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidJournal] if the journal does not start with the journal magic bytes or is truncated.
+    /// - [PatchFileError::ReadError] if the journal cannot be read or ``target`` cannot be written.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::F1337Patch;
+    /// use std::fs::OpenOptions;
+    /// use std::path::Path;
+    /// 
+    /// let mut target = OpenOptions::new().write(true).open("test.exe").unwrap();
+    /// let reverted = F1337Patch::revert_from_journal(&mut target, Path::new("test.exe.journal")).unwrap();
+    /// 
+    /// println!("Reverted bytes : {}", reverted);
+    /// ```
+    pub fn revert_from_journal<W: Write + Seek>(target: &mut W, journal_path: &std::path::Path) -> Result<usize, PatchFileError> {
+        let journal = std::fs::read(journal_path)?;
+        let entries = journal.strip_prefix(JOURNAL_MAGIC).ok_or(PatchFileError::InvalidJournal)?;
+
+        if entries.len() % 9 != 0 {
+            return Err(PatchFileError::InvalidJournal);
+        }
+        for entry in entries.chunks_exact(9).rev() {
+            let (address, original) = entry.split_at(8);
+
+            Self::write_byte_at(target, u64::from_le_bytes(address.try_into().unwrap()), original[0])?;
+        }
+
+        Ok(entries.len() / 9)
+    }
+
+    /// This applies the patches of a patch file to ``target`` while reading it, without building the whole [F1337Patch].
+    /// 
+    /// The header line is read first, then each patch line is parsed, verified and written to ``target`` right away,
+    /// so huge patch files do not need to be held in memory. Comment lines are skipped.<br/>
+    /// Unlike [F1337Patch::apply_to], the patches are not all verified before writing: if a patch fails,
+    /// the patches before it are left applied.
+    /// 
+    /// # Arguments
+    /// - ``patch_reader``: A mutable reference to any [BufRead] reading a patch file.
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of the number of applied patches or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader] for the errors reading the patch file.
+    /// - [PatchFileError::OldValueMismatch] if ``target`` does not contain the old value of a patch.
+    /// - [PatchFileError::OutOfBounds] if an address is past the end of ``target``.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut patch_reader = ">test.exe\n0000000000000001:13->37\n".as_bytes();
+    /// let mut target = Cursor::new(vec![0x00, 0x13]);
+    /// 
+    /// assert_eq!(F1337Patch::stream_apply(&mut patch_reader, &mut target).unwrap(), 1);
+    /// assert_eq!(target.into_inner(), vec![0x00, 0x37]);
+    /// ```
+    pub fn stream_apply<R: BufRead, W: Read + Write + Seek>(patch_reader: &mut R, target: &mut W) -> Result<usize, PatchFileError> {
+        let options = ParseOptions::default();
+        let mut header = String::new();
+        let mut marker = VersionMarker::new();
+        let mut applied = 0;
+
+        patch_reader.read_line(&mut header)?;
+        Self::get_filename(header)?;
+        Self::for_each_line(patch_reader, |line| {
+            if marker.take(line)? || options.is_comment(line) {
+                return Ok(());
+            }
+
+            Self::for_each_line_patch(line, &options, |patch| {
+                Self::check_old_value(target, &patch)?;
+                Self::write_byte_at(target, patch.target_address, patch.new)?;
+                applied += 1;
+                Ok(())
+            })
+        })?;
+
+        Ok(applied)
+    }
+
+    /// This applies the patches of a patch file to ``target`` while reading it, like [F1337Patch::stream_apply], gathering [StreamStats].
+    /// 
+    /// Instead of failing, patches whose old value is not found in ``target`` are counted as mismatched and skipped.<br/>
+    /// No-op patches are counted and skipped without reading ``target``. This gives a summary of the apply without a second read.
+    /// 
+    /// # Arguments
+    /// - ``patch_reader``: A mutable reference to any [BufRead] reading a patch file.
+    /// - ``target``: A mutable reference to anything that implements [Read], [Write] and [Seek], e.g. a [File].
+    /// 
+    /// # Returns
+    /// - Result of [StreamStats] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::from_bufreader] for the errors reading the patch file.
+    /// - [PatchFileError::ReadError] if ``target`` cannot be read or written.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337Patch;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut patch_reader = ">test.exe\n0000000000000001:13->37\n0000000000000000:37->37\n".as_bytes();
+    /// let stats = F1337Patch::stream_apply_stats(&mut patch_reader, &mut Cursor::new(vec![0x00, 0x13])).unwrap();
+    /// 
+    /// assert_eq!((stats.applied, stats.skipped_noop), (1, 1));
+    /// ```
+    pub fn stream_apply_stats<R: BufRead, W: Read + Write + Seek>(patch_reader: &mut R, target: &mut W) -> Result<StreamStats, PatchFileError> {
+        let options = ParseOptions::default();
+        let mut header = String::new();
+        let mut marker = VersionMarker::new();
+        let mut stats = StreamStats::default();
+
+        patch_reader.read_line(&mut header)?;
+        Self::get_filename(header)?;
+        Self::for_each_line(patch_reader, |line| {
+            if marker.take(line)? || options.is_comment(line) {
+                return Ok(());
+            }
+
+            Self::for_each_line_patch(line, &options, |patch| {
+                if patch.old == patch.new {
+                    stats.skipped_noop += 1;
+                } else if Self::read_byte_at(target, patch.target_address)? == Some(patch.old) {
+                    Self::write_byte_at(target, patch.target_address, patch.new)?;
+                    stats.applied += 1;
+                } else {
+                    stats.mismatched += 1;
+                }
+                Ok(())
+            })
+        })?;
+
+        Ok(stats)
+    }
+
+    /// This applies the patches to an in-memory buffer and returns the patched buffer.
+    /// 
+    /// The buffer is wrapped in a [std::io::Cursor] and patched with [F1337Patch::apply_to].
+    /// 
+    /// # Arguments
+    /// - ``data``: The buffer to patch.
+    /// 
+    /// # Returns
+    /// - Result of the patched buffer or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - See [F1337Patch::apply_to].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let patched = f1337patch.apply_to_cursor(vec![0x00, 0x13]).unwrap();
+    /// assert_eq!(patched, vec![0x00, 0x37]);
+    /// ```
+    pub fn apply_to_cursor(&self, data: Vec<u8>) -> Result<Vec<u8>, PatchFileError> {
+        let mut cursor = io::Cursor::new(data);
+
+        self.apply_to(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// This applies the patches to ``target`` like [F1337Patch::apply_to], only if the SHA-256 hash of ``target`` is ``expected_sha256``.
+    /// 
+    /// The whole target is hashed before anything is written, which guards against patching the wrong binary version.
+    /// 
+    /// # Arguments
+    /// - ``target``: The target to patch.
+    /// - ``expected_sha256``: The expected SHA-256 hash of the unpatched target.
+    /// 
+    /// # Returns
+    /// - Result of the number of applied patches or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::HashMismatch] if the hash of ``target`` is not ``expected_sha256``. Nothing is written.
+    /// - See [F1337Patch::apply_to].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch, PatchFileError};
+    /// use std::io::Cursor;
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// let result = f1337patch.apply_if_hash(&mut Cursor::new(vec![0x00, 0x13]), [0x00; 32]);
+    /// assert!(matches!(result, Err(PatchFileError::HashMismatch { .. })));
+    /// ```
+    #[cfg(feature = "sha2")]
+    pub fn apply_if_hash<W: Read + Write + Seek>(&self, target: &mut W, expected_sha256: [u8; 32]) -> Result<usize, PatchFileError> {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+
+        target.seek(io::SeekFrom::Start(0))?;
+        io::copy(target, &mut hasher)?;
+
+        let found: [u8; 32] = hasher.finalize().into();
+        if found != expected_sha256 {
+            return Err(PatchFileError::HashMismatch { expected: expected_sha256, found });
+        }
+        self.apply_to(target)
+    }
+
+    /// This applies the patches to the file at ``path`` like [F1337Patch::apply_to], while holding an exclusive lock on it.
+    /// 
+    /// The lock is an advisory lock: it prevents two processes using this function from patching the same file simultaneously,
+    /// but does not stop processes ignoring the lock from accessing the file. It is released once the file is patched, or on error.
+    /// 
+    /// # Arguments
+    /// - ``path``: The path of the file to patch.
+    /// 
+    /// # Returns
+    /// - Result of the number of applied patches or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the file can't be opened or locked. Contains [std::io::Error].
+    /// - See [F1337Patch::apply_to].
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// f1337patch.apply_to_path_locked("test.exe").unwrap();
+    /// ```
+    #[cfg(feature = "fs2")]
+    pub fn apply_to_path_locked<P: AsRef<std::path::Path>>(&self, path: P) -> Result<usize, PatchFileError> {
+        use fs2::FileExt;
+
+        let mut target = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+        // Blocks until any other process holding the lock releases it.
+        FileExt::lock_exclusive(&target)?;
+        let applied = self.apply_to(&mut target);
+        FileExt::unlock(&target)?;
+
+        applied
+    }
+
+    /// This serializes the [F1337Patch] into a compact binary form.
+    /// 
+    /// Patches are sorted by address, and each address is stored as the gap from the previous one in a LEB128 varint,
+    /// followed by the old and new values. This is much smaller than the text format for clustered patches.
+    /// 
+    /// The layout is:
+    /// ```text
+    /// "1337" | varint filename length | filename | varint patch count | (varint address gap | old | new)*
+    /// ```
+    /// 
+    /// Use [F1337Patch::from_compact_binary] to read it back.
+    /// 
+    /// # Returns
+    /// - A [Vec] of bytes containing the compact binary form.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// let compact = f1337patch.to_compact_binary();
+    /// assert!(compact.len() < f1337patch.to_string().len());
+    /// ```
+    pub fn to_compact_binary(&self) -> Vec<u8> {
+        let mut patches: Vec<&HexPatch> = self.patches.iter().collect();
+        let mut compact = COMPACT_MAGIC.to_vec();
+        let mut previous_address = 0;
+
+        patches.sort_by_key(|patch| patch.target_address);
+
+        Self::write_varint(&mut compact, self.target_filename.len() as u64);
+        compact.extend_from_slice(self.target_filename.as_bytes());
+        Self::write_varint(&mut compact, patches.len() as u64);
+        for patch in patches {
+            Self::write_varint(&mut compact, patch.target_address - previous_address);
+            compact.push(patch.old);
+            compact.push(patch.new);
+            previous_address = patch.target_address;
+        }
+        compact
+    }
+
+    /// This creates a new [F1337Patch] from the compact binary form written by [F1337Patch::to_compact_binary].
+    /// 
+    /// Patches are returned sorted by address.
+    /// 
+    /// # Arguments
+    /// - ``data``: The compact binary form.
+    /// 
+    /// # Returns
+    /// - Result of [F1337Patch] or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::WrongFormat] if the data is truncated, corrupted or not in the compact binary form.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// 
+    /// let decoded = F1337Patch::from_compact_binary(&f1337patch.to_compact_binary()).unwrap();
+    /// assert_eq!(decoded.patches, f1337patch.patches);
+    /// ```
+    pub fn from_compact_binary(data: &[u8]) -> Result<F1337Patch, PatchFileError> {
+        let mut data = data.strip_prefix(COMPACT_MAGIC.as_slice()).ok_or(PatchFileError::WrongFormat)?;
+        let filename_len = Self::read_varint(&mut data)?;
+        let filename_len = usize::try_from(filename_len).map_err(|_| PatchFileError::WrongFormat)?;
+
+        if filename_len > data.len() {
+            return Err(PatchFileError::WrongFormat);
+        }
+        let (filename, mut data) = data.split_at(filename_len);
+        let filename = String::from_utf8(filename.to_vec()).map_err(|_| PatchFileError::WrongFormat)?;
+        let mut f1337patch = F1337Patch::new(filename);
+        let count = Self::read_varint(&mut data)?;
+        let mut address: u64 = 0;
+
+        for _ in 0..count {
+            address = address.checked_add(Self::read_varint(&mut data)?).ok_or(PatchFileError::WrongFormat)?;
+            match data {
+                [old, new, rest @ ..] => {
+                    f1337patch.patches.push(HexPatch::new(address, *old, *new));
+                    data = rest;
+                },
+                _ => return Err(PatchFileError::WrongFormat),
+            }
+        }
+        if !data.is_empty() {
+            return Err(PatchFileError::WrongFormat);
+        }
+        Ok(f1337patch)
+    }
+
+    /// This function appends ``value`` to ``out`` as an unsigned LEB128 varint.
+    fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    /// This function reads an unsigned LEB128 varint from the start of ``data`` and advances it past the varint.
+    fn read_varint(data: &mut &[u8]) -> Result<u64, PatchFileError> {
+        let mut value: u64 = 0;
+
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = data.split_first().ok_or(PatchFileError::WrongFormat)?;
+            let bits = u64::from(byte & 0x7F);
+
+            // Reject bits that do not fit in a u64.
+            if bits << shift >> shift != bits {
+                return Err(PatchFileError::WrongFormat);
+            }
+            value |= bits << shift;
+            *data = rest;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(PatchFileError::WrongFormat)
+    }
+
+    /// This returns the indices of the patches already applied to ``data``.
+    /// 
+    /// A patch is already applied if the byte of ``data`` at its address is its [new value](HexPatch::new).<br/>
+    /// Patches with an address past the end of ``data`` are never considered applied.
+    /// 
+    /// # Arguments
+    /// - ``data``: The content of the target binary.
+    /// 
+    /// # Returns
+    /// - A [Vec] of indices into [F1337Patch::patches], in ascending order.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, HexPatch};
+    /// 
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+    /// f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+    /// 
+    /// assert_eq!(f1337patch.redundant_against(&[0x13, 0x37]), vec![1]);
+    /// ```
+    pub fn redundant_against(&self, data: &[u8]) -> Vec<usize> {
+        self.patches.iter()
+            .enumerate()
+            .filter(|(_, patch)| {
+                usize::try_from(patch.target_address).ok()
+                    .and_then(|address| data.get(address))
+                    .is_some_and(|byte| *byte == patch.new)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// This function reads the byte at ``address`` of ``target``, or [None] if ``address`` is past its end.
+    fn read_byte_at<R: Read + Seek>(target: &mut R, address: u64) -> Result<Option<u8>, PatchFileError> {
+        let mut byte = [0u8; 1];
+
+        target.seek(io::SeekFrom::Start(address))?;
+        match target.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    /// This function writes ``value`` at ``address`` of ``target``.
+    fn write_byte_at<W: Write + Seek>(target: &mut W, address: u64, value: u8) -> Result<(), PatchFileError> {
+        target.seek(io::SeekFrom::Start(address))?;
+        target.write_all(&[value])?;
+        Ok(())
+    }
+
+    /// This function checks that ``target`` contains the old value of ``patch`` at its address.
+    fn check_old_value<R: Read + Seek>(target: &mut R, patch: &HexPatch) -> Result<(), PatchFileError> {
+        match Self::read_byte_at(target, patch.target_address)? {
+            None => Err(PatchFileError::OutOfBounds(patch.target_address)),
+            Some(found) if found != patch.old => Err(PatchFileError::OldValueMismatch {
+                address: patch.target_address,
+                expected: patch.old,
+                found,
+            }),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// This function extract filename from the first line of the patch file.
+    /// The first line start with ">" and followed by the target file name.
+    fn get_filename(first_line: String) -> Result<String, PatchFileError> {
+        if !first_line.starts_with('>') {
+            return Err(Self::missing_header_error(&first_line));
+        }
+        
+        // This returns the filename. Trim the end to remove the \n (and \r\n on windows).
+        Ok(first_line[1..].trim_end().to_string())
+    }
+
+    /// This function builds the error of a first line which is not a header line, quoting its first 20 characters.
+    /// 
+    /// Forgetting the header line is a common mistake, so the error shows what was found instead.
+    fn missing_header_error(first_line: &str) -> PatchFileError {
+        let found: String = first_line.trim_end_matches(['\r', '\n']).chars().take(20).collect();
+
+        PatchFileError::InvalidHeader(format!("expected header line starting with '>', found: {}", found))
+    }
+}
+
+/// Implement [std::fmt::Display] for [F1337Patch]
+impl std::fmt::Display for F1337Patch {
+    /// This is the implementation of [std::fmt::Display::fmt] for [F1337Patch].
+    /// 
+    /// The patch is written in the patch file format described in [F1337Patch]: the header line followed by one line per patch.<br/>
+    /// Hex values are written in uppercase and every line, including the last one, ends with ``\n``.<br/>
+    /// Patches are written in the order they were read or added: they are never sorted, so the output order matches the input order.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, ">{}", self.target_filename)?;
+        for patch in &self.patches {
+            writeln!(f, "{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// This is used to create representation of a patch document targeting several files.
+/// 
+/// A multi-file patch document is a sequence of sections in the [F1337Patch] format:<br/>
+/// each line starting with ``>`` starts the patches of a new target file.
+/// 
+/// # Example
+/// ```text
+/// >game.exe
+/// 0000000000AF0200:13->37
+/// >engine.dll
+/// 0000000000001000:74->EB
+/// ```
+/// 
+/// The patches of each target file are stored in a vector of [F1337Patch].
+#[derive(Debug, Default)]
+pub struct F1337PatchSet {
+    /// Patches of each target file, in document order.
+    pub files: Vec<F1337Patch>,
+}
+
+impl F1337PatchSet {
+    /// This creates an new empty [F1337PatchSet].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, F1337PatchSet};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// 
+    /// f1337patchset.add_file(F1337Patch::new("game.exe".to_string()));
+    /// ```
+    pub fn new() -> Self {
+        F1337PatchSet {
+            files: Vec::new(),
+        }
+    }
+
+    /// This adds the patches of a target file to the [F1337PatchSet].
+    /// 
+    /// # Arguments
+    /// - ``f1337patch``: A [F1337Patch]. Can be created with [F1337Patch::new].
+    pub fn add_file(&mut self, f1337patch: F1337Patch) {
+        self.files.push(f1337patch);
+    }
+
+    /// This returns the patches of the given target file.
+    /// 
+    /// # Arguments
+    /// - ``filename``: The target file name, as written in its header line.
+    /// 
+    /// # Returns
+    /// - The patches of the first [F1337Patch] targeting ``filename``, or [None] if there is none.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, F1337PatchSet, HexPatch};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// let mut f1337patch = F1337Patch::new("game.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patchset.add_file(f1337patch);
+    /// 
+    /// assert_eq!(f1337patchset.patches_for("game.exe").unwrap().len(), 1);
+    /// ```
+    pub fn patches_for(&self, filename: &str) -> Option<&[HexPatch]> {
+        self.files.iter()
+            .find(|f1337patch| f1337patch.target_filename == filename)
+            .map(|f1337patch| f1337patch.patches.as_slice())
+    }
+
+    /// This lists the target file names of a multi-file patch document without building the patches.
+    /// 
+    /// Only the header lines are decoded, patch lines are skipped without being validated.
+    /// 
+    /// # Arguments
+    /// - ``reader``: A mutable reference to any [BufRead].
+    /// 
+    /// # Returns
+    /// - Result of the target file names, in document order, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::ReadError] if the document can't be read or a header is not valid UTF-8. Contains [std::io::Error].
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::F1337PatchSet;
+    /// use std::io::Cursor;
+    /// 
+    /// let mut document = Cursor::new(">game.exe\n0000000000AF0200:13->37\n>engine.dll\n0000000000001000:74->EB\n");
+    /// 
+    /// assert_eq!(F1337PatchSet::list_targets(&mut document).unwrap(), vec!["game.exe", "engine.dll"]);
+    /// ```
+    pub fn list_targets<R: BufRead>(reader: &mut R) -> Result<Vec<String>, PatchFileError> {
+        let mut targets = Vec::new();
+        let mut line = Vec::new();
+
+        while reader.read_until(b'\n', &mut line)? != 0 {
+            if line.starts_with(b">") {
+                let header = String::from_utf8(std::mem::take(&mut line))
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+                targets.push(F1337Patch::get_filename(header)?);
+            }
+            line.clear();
+        }
+        Ok(targets)
+    }
+
+    /// This applies the patches of each target file to the file of the same name in ``dir``.
+    /// 
+    /// Each file is patched with [F1337Patch::apply_to]: its old values are all checked before anything is written to it.<br/>
+    /// Target files which do not exist in ``dir`` are skipped and left out of the returned map,
+    /// so comparing its keys with [F1337PatchSet::files] reports the missing files.
+    /// 
+    /// Every target file name must be a relative path made of plain names, so that no file outside of ``dir`` is opened:
+    /// names such as ``../boot.ini`` or ``/etc/passwd`` are rejected before any file is patched.
+    /// 
+    /// # Arguments
+    /// - ``dir``: The directory containing the target files.
+    /// 
+    /// # Returns
+    /// - Result of a map of each patched file name to the number of bytes changed in it, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidHeader] if a target file name is not a relative path inside ``dir``. Nothing is patched.
+    /// - See [F1337Patch::apply_to]. Files patched before the error are left patched.
+    /// - [PatchFileError::ReadError] if a target file exists but cannot be opened.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, F1337PatchSet, HexPatch};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patchset.add_file(f1337patch);
+    /// 
+    /// let changed = f1337patchset.apply_to_dir("game").unwrap();
+    /// println!("Bytes changed in test.exe : {:?}", changed.get("test.exe"));
+    /// ```
+    pub fn apply_to_dir<P: AsRef<std::path::Path>>(&self, dir: P) -> Result<HashMap<String, usize>, PatchFileError> {
+        let paths = self.target_paths(dir.as_ref())?;
+        let mut changed = HashMap::new();
+
+        for (f1337patch, path) in self.files.iter().zip(paths) {
+            let mut target = match std::fs::OpenOptions::new().read(true).write(true).open(path) {
+                Ok(target) => target,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+
+            f1337patch.apply_to(&mut target)?;
+            *changed.entry(f1337patch.target_filename.clone()).or_insert(0) += Self::changed_bytes(f1337patch);
+        }
+        Ok(changed)
+    }
+
+    /// This applies the patches of each target file to the file of the same name in ``dir``, with all-or-nothing semantics.
+    /// 
+    /// Unlike [F1337PatchSet::apply_to_dir], every target file is verified before any is written:
+    /// the patches are first applied to an in-memory copy of each file, so a mismatching old value or an out of bounds
+    /// address in any file leaves the whole directory untouched.<br/>
+    /// Sections targeting the same file are verified in order, each against the result of the previous ones.
+    /// Target files which do not exist in ``dir`` are skipped and left out of the returned map, like [F1337PatchSet::apply_to_dir].
+    /// 
+    /// Target file names are checked like [F1337PatchSet::apply_to_dir]: no file outside of ``dir`` is read or written.
+    /// 
+    /// # Arguments
+    /// - ``dir``: The directory containing the target files.
+    /// 
+    /// # Returns
+    /// - Result of a map of each patched file name to the number of bytes changed in it, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidHeader] if a target file name is not a relative path inside ``dir``. Nothing is patched.
+    /// - See [F1337Patch::apply_to]. No file is written when a patch fails to verify.
+    /// - [PatchFileError::ReadError] if a target file exists but cannot be read or written.
+    ///   An error while writing, after the verification, may leave the files before it patched.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, F1337PatchSet, HexPatch};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patchset.add_file(f1337patch);
+    /// 
+    /// match f1337patchset.apply_to_dir_atomic("game") {
+    ///     Ok(changed) => println!("Bytes changed in test.exe : {:?}", changed.get("test.exe")),
+    ///     Err(error) => println!("Nothing was patched : {:?}", error),
+    /// }
+    /// ```
+    pub fn apply_to_dir_atomic<P: AsRef<std::path::Path>>(&self, dir: P) -> Result<HashMap<String, usize>, PatchFileError> {
+        let paths = self.target_paths(dir.as_ref())?;
+        let mut copies: HashMap<&str, io::Cursor<Vec<u8>>> = HashMap::new();
+        let mut changed = HashMap::new();
+
+        // Verify every section against an in-memory copy of its target file.
+        for (f1337patch, path) in self.files.iter().zip(&paths) {
+            let copy = match copies.entry(&f1337patch.target_filename) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    match std::fs::read(path) {
+                        Ok(content) => entry.insert(io::Cursor::new(content)),
+                        Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+            };
+
+            f1337patch.apply_to(copy)?;
+            *changed.entry(f1337patch.target_filename.clone()).or_insert(0) += Self::changed_bytes(f1337patch);
+        }
+
+        // Everything verified: patch the files themselves.
+        for (f1337patch, path) in self.files.iter().zip(&paths) {
+            if !copies.contains_key(f1337patch.target_filename.as_str()) {
+                continue;
+            }
+            let mut target = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+            f1337patch.apply_to(&mut target)?;
+        }
+        Ok(changed)
+    }
+
+    /// This function joins the target file name of every section to ``dir``.
+    /// 
+    /// Only relative paths made of plain names are accepted: ``..``, roots and prefixes could point outside of ``dir``,
+    /// and an absolute path would replace ``dir`` entirely.
+    fn target_paths(&self, dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>, PatchFileError> {
+        self.files.iter().map(|f1337patch| {
+            let filename = std::path::Path::new(&f1337patch.target_filename);
+            let is_plain = filename.components().all(|component| matches!(component, std::path::Component::Normal(_)));
+
+            if f1337patch.target_filename.is_empty() || !is_plain {
+                return Err(PatchFileError::InvalidHeader(
+                    format!("target file name is not a relative path inside the directory: {}", f1337patch.target_filename)
+                ));
+            }
+            Ok(dir.join(filename))
+        }).collect()
+    }
+
+    /// This function counts the bytes changed by applying the patches of a target file.
+    /// Every patch of an address checked the same old value, so the byte changes if the last written value differs.
+    fn changed_bytes(f1337patch: &F1337Patch) -> usize {
+        f1337patch.last_patch_per_address()
+            .values()
+            .filter(|patch| patch.old != patch.new)
+            .count()
+    }
+
+    /// This finds the addresses patched in two different target files, a light lint for multi-file patch documents.
+    /// 
+    /// Patching the same address in two files is usually benign, but is occasionally a copy-paste mistake.<br/>
+    /// Sections targeting the same file name are not compared with each other.
+    /// 
+    /// # Returns
+    /// - The ``(first_filename, second_filename, address)`` clashes, in the order of [F1337PatchSet::files], then by address.
+    ///   Each address is reported once per pair of sections.
+    /// 
+    /// # Example
+    /// ```rust
+    /// use lib1337patch::{F1337Patch, F1337PatchSet, HexPatch};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// let mut game = F1337Patch::new("game.exe".to_string());
+    /// let mut engine = F1337Patch::new("engine.dll".to_string());
+    /// 
+    /// game.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// engine.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patchset.add_file(game);
+    /// f1337patchset.add_file(engine);
+    /// 
+    /// assert_eq!(
+    ///     f1337patchset.find_cross_file_address_clashes(),
+    ///     vec![("game.exe".to_string(), "engine.dll".to_string(), 0x0000000000AF0200)]
+    /// );
+    /// ```
+    pub fn find_cross_file_address_clashes(&self) -> Vec<(String, String, u64)> {
+        let addresses: Vec<std::collections::BTreeSet<u64>> = self.files.iter()
+            .map(|f1337patch| f1337patch.patches.iter().map(|patch| patch.target_address).collect())
+            .collect();
+        let mut clashes = Vec::new();
+
+        for (i, first) in self.files.iter().enumerate() {
+            for (j, second) in self.files.iter().enumerate().skip(i + 1) {
+                if first.target_filename == second.target_filename {
+                    continue;
+                }
+                for address in addresses[i].intersection(&addresses[j]) {
+                    clashes.push((first.target_filename.clone(), second.target_filename.clone(), *address));
+                }
+            }
+        }
+        clashes
+    }
+
+    /// This writes the patches of each target file to a separate ``.1337`` patch file in ``dir``.
+    /// 
+    /// The patch file of a target is named after the target file name followed by ``.1337``, e.g. ``test.exe.1337``.<br/>
+    /// The file name is sanitized to stay in ``dir``: path separators, ``:`` and control characters are replaced by ``_``,
+    /// and names made only of dots are replaced by ``_``. The header line keeps the original target file name.<br/>
+    /// Sections targeting the same file are merged into a single patch file, in order.
+    /// 
+    /// # Arguments
+    /// - ``dir``: The existing directory to write the patch files to. Existing patch files are overwritten.
+    /// 
+    /// # Returns
+    /// - Result of the paths of the written patch files, in the order the target files first appear, or [PatchFileError].
+    /// 
+    /// # Errors
+    /// - [PatchFileError::InvalidHeader] if two different target file names are sanitized to the same patch file name,
+    ///   e.g. ``a/b`` and ``a:b``. Nothing is written.
+    /// - [PatchFileError::ReadError] if a patch file cannot be written.
+    /// 
+    /// # Example
+    /// ```rust,no_run
+    /// use lib1337patch::{F1337Patch, F1337PatchSet, HexPatch};
+    /// 
+    /// let mut f1337patchset = F1337PatchSet::new();
+    /// let mut f1337patch = F1337Patch::new("test.exe".to_string());
+    /// 
+    /// f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+    /// f1337patchset.add_file(f1337patch);
+    /// 
+    /// let paths = f1337patchset.write_split("patches").unwrap();
+    /// println!("Written : {:?}", paths);
+    /// ```
+    pub fn write_split<P: AsRef<std::path::Path>>(&self, dir: P) -> Result<Vec<std::path::PathBuf>, PatchFileError> {
+        let mut merged: Vec<F1337Patch> = Vec::new();
+
+        for f1337patch in &self.files {
+            match merged.iter_mut().find(|target| target.target_filename == f1337patch.target_filename) {
+                Some(target) => target.patches.extend_from_slice(&f1337patch.patches),
+                None => {
+                    let mut target = F1337Patch::new(f1337patch.target_filename.clone());
+
+                    target.format_version = f1337patch.format_version;
+                    target.patches = f1337patch.patches.clone();
+                    merged.push(target);
+                },
+            }
+        }
+
+        // Check every patch file name before writing, so that no patch file overwrites another one.
+        let filenames: Vec<String> = merged.iter()
+            .map(|target| format!("{}.1337", Self::sanitize_filename(&target.target_filename)))
+            .collect();
+        for (i, filename) in filenames.iter().enumerate() {
+            if let Some(j) = filenames[..i].iter().position(|other| other == filename) {
+                return Err(PatchFileError::InvalidHeader(format!(
+                    "target file names {} and {} are both written to {}", merged[j].target_filename, merged[i].target_filename, filename
+                )));
+            }
+        }
+
+        let mut paths = Vec::with_capacity(merged.len());
+        for (target, filename) in merged.iter().zip(filenames) {
+            let path = dir.as_ref().join(filename);
+
+            target.save_to_file(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// This function turns a target file name into a file name which cannot escape its directory.
+    fn sanitize_filename(filename: &str) -> String {
+        let sanitized: String = filename.chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+
+        match sanitized.chars().all(|c| c == '.') {
+            true => "_".to_string(),
+            false => sanitized,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::needless_late_init, clippy::unnecessary_mut_passed, clippy::unnecessary_to_owned)]
+mod test {
+    use super::*;
+    use tempfile::tempfile;
+    use std::io::Write;
+    
+        // TODO : Add some fuzzing for [F1337Patch::new] and [F1337Patch::from_filepatch] to test more cases.
+        // TODO : Add more fuzzing for [F1337Patch::check_patch_line_format] to test more cases.
+    
+    #[test]
+    fn test_f1337patch_new() {
+        let f1337path = F1337Patch::new("test.exe".to_string());
+
+        assert_eq!(f1337path.target_filename, "test.exe");
+        assert_eq!(f1337path.patches.len(), 0);
+    }
+
+    #[test]
+    fn test_f1337patch_from_filepatch() {
+        let mut dummy_file = tempfile().unwrap();
+        let f1337path: F1337Patch;
+        let dummy_patches: Vec<HexPatch>;
+        
+        writeln!(dummy_file, ">test.exe").unwrap();
+        writeln!(dummy_file, "0000000000AF0200:13->37").unwrap();
+        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+
+        f1337path = F1337Patch::from_patchfile(&mut dummy_file).unwrap();
+
+        assert_eq!(f1337path.target_filename, "test.exe");
+        assert_eq!(f1337path.patches.len(), 2);
+
+        dummy_patches = vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ];
+
+        assert_eq!(dummy_patches, f1337path.patches);
+
+        drop(dummy_file);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader() {
+        let mut dummy_file = tempfile().unwrap();
+        let f1337path: F1337Patch;
+        let dummy_patches: Vec<HexPatch>;
+        
+        writeln!(dummy_file, ">test.exe").unwrap();
+        writeln!(dummy_file, "0000000000AF0200:13->37").unwrap();
+        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+
+        f1337path = F1337Patch::from_bufreader(&mut io::BufReader::new(&dummy_file)).unwrap();
+
+        assert_eq!(f1337path.target_filename, "test.exe");
+        assert_eq!(f1337path.patches.len(), 2);
+
+        dummy_patches = vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ];
+
+        assert_eq!(dummy_patches, f1337path.patches);
+
+        drop(dummy_file);
+    }
+
+    #[test]
+    fn test_nop_range() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.nop_range(0xAF0200, &[0x74, 0x05, 0x13]).unwrap();
+
+        assert_eq!(f1337path.patches.len(), 3);
+        assert!(f1337path.patches.iter().all(|patch| patch.new == X86_NOP));
+        assert_eq!(f1337path.patches[2], HexPatch::new(0xAF0202, 0x13, 0x90));
+        assert_eq!(HexPatch::nop(0xAF0200, 0x74), HexPatch::new(0xAF0200, 0x74, 0x90));
+    }
+
+    #[test]
+    fn test_nop_range_address_overflow() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.nop_range(u64::MAX, &[0x00]).unwrap();
+        assert_eq!(f1337path.patches, vec![HexPatch::new(u64::MAX, 0x00, 0x90)]);
+
+        let address_overflow = f1337path.nop_range(u64::MAX - 1, &[0x00, 0x01, 0x02]).unwrap_err();
+        assert_eq!(address_overflow, PatchFileError::AddressOverflow(u64::MAX - 1));
+        assert_eq!(f1337path.patches.len(), 1);
+
+        f1337path.nop_range(u64::MAX, &[]).unwrap();
+        assert_eq!(f1337path.patches.len(), 1);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_lenient() {
+        let mut dummy_file = tempfile().unwrap();
+        let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+
+        writeln!(dummy_file, ">test.exe").unwrap();
+        writeln!(dummy_file, "0000000000AF0200 : 13 -> 37").unwrap();
+        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+        writeln!(dummy_file, "0000000000AF0208:\t13->  37 ").unwrap();
+
+        let f1337path = F1337Patch::from_bufreader_with_options(&mut io::BufReader::new(&dummy_file), &options).unwrap();
+
+        let dummy_patches = vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+            HexPatch::new(0xAF0208, 0x13, 0x37),
+        ];
+
+        assert_eq!(dummy_patches, f1337path.patches);
+
+        // Strict parsing stays the default.
+        let wrong_format = F1337Patch::from_bufreader(&mut io::BufReader::new(&dummy_file)).unwrap_err();
+        assert_eq!(wrong_format, PatchFileError::WrongFormat);
+    }
+
+    #[test]
+    fn test_get_hex_patch_from_line_lenient_wrong_format() {
+        let lines = vec![
+            "0000000000AF0200 13 -> 37",
+            "0000000000AF0200 : 13 37",
+            "AF0200 : 13 -> 37",
+            "0000000000AF0200 : 1 3 -> 37",
+            "0000000000AF0200 : 13 -> ZA",
+        ];
+
+        for line in lines {
+            let wrong_format = F1337Patch::get_hex_patch_from_line_lenient(line).unwrap_err();
+            assert_eq!(wrong_format, PatchFileError::WrongFormat);
+        };
+    }
+
+    #[test]
+    fn test_f1337patch_to_string() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+
+        assert_eq!(f1337path.to_string(), ">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n");
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_content_id() {
+        let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+        let strict = F1337Patch::from_bufreader(
+            &mut io::Cursor::new(">test.exe\n0000000000AF0200:13->37\n0000000000af0206:37->37\n")
+        ).unwrap();
+        let lenient = F1337Patch::from_bufreader_with_options(
+            &mut io::Cursor::new(">test.exe\r\n0000000000AF0200 : 13 -> 37\r\n0000000000AF0206:37 -> 37"),
+            &options,
+        ).unwrap();
+        let mut other = F1337Patch::new("test.exe".to_string());
+
+        other.add_patch(HexPatch::new(0xAF0200, 0x13, 0x38));
+
+        assert_eq!(strict.content_id(), lenient.content_id());
+        assert_ne!(strict.content_id(), other.content_id());
+    }
+
+    #[test]
+    fn test_apply_to_cursor() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut expected = vec![0u8; 16];
+
+        f1337path.add_patch(HexPatch::new(0x02, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x0F, 0x74, 0xEB));
+
+        let mut data = vec![0u8; 16];
+        data[0x02] = 0x13;
+        data[0x0F] = 0x74;
+        expected[0x02] = 0x37;
+        expected[0x0F] = 0xEB;
+
+        assert_eq!(f1337path.apply_to_cursor(data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_apply_to_errors() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x00]);
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+
+        let mismatch = f1337path.apply_to(&mut target).unwrap_err();
+        assert_eq!(mismatch, PatchFileError::OldValueMismatch { address: 0x01, expected: 0x74, found: 0x00 });
+        // Nothing is written when the verification fails.
+        assert_eq!(target.get_ref(), &vec![0x13, 0x00]);
+
+        f1337path.add_patch(HexPatch::new(0x10, 0x13, 0x37));
+        target.get_mut()[1] = 0x74;
+
+        let out_of_bounds = f1337path.apply_to(&mut target).unwrap_err();
+        assert_eq!(out_of_bounds, PatchFileError::OutOfBounds(0x10));
+        assert_eq!(target.get_ref(), &vec![0x13, 0x74]);
+    }
+
+    #[test]
+    fn test_diagnose_line() {
+        assert_eq!(F1337Patch::diagnose_line("0000000000AF0200:13->37"), vec![]);
+        assert_eq!(F1337Patch::diagnose_line("0000000000AF02KK;13=>3Z"), vec![
+            LineDiagnostic::MissingColon,
+            LineDiagnostic::MissingArrow,
+            LineDiagnostic::NonHexAddress,
+            LineDiagnostic::NonHexNew,
+        ]);
+        assert_eq!(F1337Patch::diagnose_line("0000000000AF0200:1G->"), vec![
+            LineDiagnostic::WrongLength(21),
+            LineDiagnostic::NonHexOld,
+        ]);
+        assert_eq!(F1337Patch::diagnose_line("é"), vec![
+            LineDiagnostic::WrongLength(2),
+            LineDiagnostic::MissingColon,
+            LineDiagnostic::MissingArrow,
+            LineDiagnostic::NonHexAddress,
+        ]);
+    }
+
+    #[test]
+    fn test_compact_binary_round_trip() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        for address in [0xAF0210, 0xAF0200, 0xAF0201, 0xAF0202, 0xAF0206, 0xFFFFFFFFFFFFFFFF, 0x00] {
+            f1337path.add_patch(HexPatch::new(address, 0x13, 0x37));
+        }
+
+        let compact = f1337path.to_compact_binary();
+        let decoded = F1337Patch::from_compact_binary(&compact).unwrap();
+
+        f1337path.patches.sort_by_key(|patch| patch.target_address);
+        assert_eq!(decoded.target_filename, f1337path.target_filename);
+        assert_eq!(decoded.patches, f1337path.patches);
+        assert!(compact.len() * 3 < f1337path.to_string().len());
+    }
+
+    #[test]
+    fn test_from_compact_binary_wrong_format() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+
+        let compact = f1337path.to_compact_binary();
+        let mut trailing = compact.clone();
+        trailing.push(0x00);
+
+        for data in [&compact[..compact.len() - 1], &compact[1..], &trailing, &[0x31, 0x33, 0x33, 0x37, 0xFF]] {
+            assert_eq!(F1337Patch::from_compact_binary(data).unwrap_err(), PatchFileError::WrongFormat);
+        }
+    }
+
+    #[test]
+    fn test_without_redundant() {
+        let mut baseline = F1337Patch::new("test.exe".to_string());
+        let mut layer = F1337Patch::new("test.exe".to_string());
+
+        baseline.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        baseline.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        baseline.add_patch(HexPatch::new(0xAF0208, 0x13, 0x37));
+        layer.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        layer.add_patch(HexPatch::new(0xAF0204, 0x13, 0x37));
+        layer.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        layer.add_patch(HexPatch::new(0xAF0208, 0x13, 0x90));
+
+        let remaining = layer.without_redundant(&baseline);
+
+        assert_eq!(remaining.target_filename, "test.exe");
+        assert_eq!(remaining.patches, vec![
+            HexPatch::new(0xAF0204, 0x13, 0x37),
+            HexPatch::new(0xAF0208, 0x13, 0x90),
+        ]);
+    }
+
+    #[test]
+    fn test_check_patch_line_format_wrong_format() {
+        let lines = vec![
+            "0000000000AF0200:13->3",
+            "000000AF0200:13->32",
+            "0000000000AF020089:13->3A",
+            "0000000000AF0200:13->ZA",
+            "0000000000AF02KK:13->3A",
+        ];
+
+        for line in lines {
+            let wrong_format = F1337Patch::check_patch_line_format(&line.to_string()).unwrap_err();
+            assert_eq!(wrong_format, PatchFileError::WrongFormat);
+        };
+    }
+
+    #[test]
+    fn test_check_header_format() {
+        for line in [">test.exe", ">test.exe\n", "> my game.exe\r\n"] {
+            assert_eq!(F1337Patch::check_header_format(line), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_check_header_format_invalid() {
+        let lines = vec![
+            ("test.exe", "header must start with '>'"),
+            ("", "header must start with '>'"),
+            (" >test.exe", "header must start with '>'"),
+            (">", "header does not contain a target file name"),
+            (">  \r\n", "header does not contain a target file name"),
+        ];
+
+        for (line, reason) in lines {
+            let invalid_header = F1337Patch::check_header_format(line).unwrap_err();
+            assert_eq!(invalid_header, PatchFileError::InvalidHeader(reason.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_get_filename_wrong_format() {
+        let wrong_format = F1337Patch::get_filename("test.exe".to_string()).unwrap_err();
+
+        assert_eq!(wrong_format, PatchFileError::InvalidHeader("expected header line starting with '>', found: test.exe".to_string()));
+    }
+
+    #[test]
+    fn test_missing_header_error_quotes_found_content() {
+        let content = "0000000000AF0200:13->37\n";
+
+        let error = F1337Patch::from_bufreader(&mut io::Cursor::new(content)).unwrap_err();
+        let message = format!("{:?}", error);
+
+        assert!(message.contains("expected header line starting with '>'"));
+        assert!(message.contains("found: 0000000000AF0200:13-"));
+        assert!(!message.contains("->37"));
+
+        let error = F1337Patch::from_bufreader_os(&mut io::Cursor::new(content)).unwrap_err();
+        assert!(format!("{:?}", error).contains("found: 0000000000AF0200:13-"));
+    }
+
+
+    #[test]
+    fn test_redundant_against() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let data = [0x13, 0x00, 0x37, 0x74];
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x02, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x03, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0x10, 0x13, 0x37));
+
+        assert_eq!(f1337path.redundant_against(&data), vec![1]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_missing_header() {
+        let options = ParseOptions { allow_missing_header: true, ..ParseOptions::default() };
+        let content = "0000000000AF0200:13->37\r\n0000000000AF0206:37->37\n";
+
+        let f1337path = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(content), &options).unwrap();
+
+        assert_eq!(f1337path.target_filename, "");
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+
+        // A header is still used when present.
+        let f1337path = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(">test.exe\n0000000000AF0200:13->37\n"), &options).unwrap();
+        assert_eq!(f1337path.target_filename, "test.exe");
+        assert_eq!(f1337path.patches.len(), 1);
+
+        // And required by default.
+        let wrong_format = F1337Patch::from_bufreader(&mut io::Cursor::new(content)).unwrap_err();
+        assert!(matches!(wrong_format, PatchFileError::InvalidHeader(_)));
+    }
+
+
+    #[test]
+    fn test_apply_to_logged() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x37, 0x74]);
+        let mut log = Vec::new();
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x37, 0x37));
+        f1337path.add_patch(HexPatch::new(0x02, 0x74, 0xEB));
+
+        assert_eq!(f1337path.apply_to_logged(&mut target, &mut log).unwrap(), 3);
+        assert_eq!(target.into_inner(), vec![0x37, 0x37, 0xEB]);
+        assert_eq!(String::from_utf8(log).unwrap(), concat!(
+            "0000000000000000:13->37 applied\n",
+            "0000000000000001:37->37 applied\n",
+            "0000000000000002:74->EB applied\n",
+        ));
+    }
+
+    #[test]
+    fn test_apply_to_logged_mismatch() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x00]);
+        let mut log = Vec::new();
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+
+        assert!(f1337path.apply_to_logged(&mut target, &mut log).is_err());
+        assert_eq!(target.into_inner(), vec![0x13, 0x00]);
+        assert_eq!(String::from_utf8(log).unwrap(), "0000000000000001:74->EB mismatch, found 00\n");
+    }
+
+
+    #[test]
+    fn test_partition_by() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0201, 0x74, 0x90));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0208, 0x05, 0x90));
+
+        let (nops, others) = f1337path.partition_by(|patch| patch.new == 0x90);
+
+        assert_eq!(nops.target_filename, "test.exe");
+        assert_eq!(others.target_filename, "test.exe");
+        assert_eq!(nops.patches, vec![
+            HexPatch::new(0xAF0201, 0x74, 0x90),
+            HexPatch::new(0xAF0208, 0x05, 0x90),
+        ]);
+        assert_eq!(others.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+    }
+
+
+    #[test]
+    fn test_address_gaps() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        assert_eq!(f1337path.address_gaps(), vec![]);
+
+        f1337path.add_patch(HexPatch::new(0x110, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x100, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x102, 0x13, 0x37));
+
+        assert_eq!(f1337path.address_gaps(), vec![2, 14]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_normalize_eol() {
+        let mut dummy_file = tempfile().unwrap();
+
+        dummy_file.write_all(concat!(
+            ">test.exe\r\n",
+            "0000000000AF0200:13->37\n",
+            "0000000000AF0206:37->37\r\r\n",
+            "0000000000AF0208:13->37\r\n",
+            "0000000000AF020A:13->37\r",
+        ).as_bytes()).unwrap();
+
+        let f1337path = F1337Patch::from_bufreader_normalize_eol(&mut io::BufReader::new(&dummy_file)).unwrap();
 
         assert_eq!(f1337path.target_filename, "test.exe");
-        assert_eq!(f1337path.patches.len(), 0);
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+            HexPatch::new(0xAF0208, 0x13, 0x37),
+            HexPatch::new(0xAF020A, 0x13, 0x37),
+        ]);
+
+        let wrong_format = F1337Patch::from_bufreader(&mut io::BufReader::new(&dummy_file)).unwrap_err();
+        assert_eq!(wrong_format, PatchFileError::WrongFormat);
+    }
+
+
+    #[test]
+    fn test_get() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+
+        assert_eq!(f1337path.get(1), Some(&HexPatch::new(0xAF0206, 0x37, 0x37)));
+        assert_eq!(f1337path.get(2), None);
+    }
+
+
+    #[test]
+    fn test_insert_patch() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        f1337path.insert_patch(1, HexPatch::new(0xAF0204, 0x74, 0xEB));
+
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0204, 0x74, 0xEB),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot insert patch at index 3 in a patch set of 2 patches")]
+    fn test_insert_patch_out_of_range() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        f1337path.insert_patch(3, HexPatch::new(0xAF0204, 0x74, 0xEB));
+    }
+
+
+    #[test]
+    fn test_reverse_where() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0xAF0208, 0x05, 0x90));
+
+        f1337path.reverse_where(|patch| patch.target_address > 0xAF0200);
+
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0xEB, 0x74),
+            HexPatch::new(0xAF0208, 0x90, 0x05),
+        ]);
+    }
+
+
+    #[test]
+    fn test_is_single_page() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        assert!(f1337path.is_single_page(0x1000));
+
+        f1337path.add_patch(HexPatch::new(0xAF0FFF, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0000, 0x37, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x74, 0xEB));
+
+        assert!(f1337path.is_single_page(0x1000));
+
+        f1337path.add_patch(HexPatch::new(0xAF1000, 0x05, 0x90));
+
+        assert!(!f1337path.is_single_page(0x1000));
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_comments() {
+        let content = ">test.exe\n# comment\n0000000000AF0200:13->37\n  ; comment\n0000000000AF0206:37->37\n";
+
+        let f1337path = F1337Patch::from_bufreader(&mut io::Cursor::new(content)).unwrap();
+
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_custom_comment_prefix() {
+        let options = ParseOptions { comment_prefixes: &["//"], ..ParseOptions::default() };
+        let content = ">test.exe\n// comment\n0000000000AF0200:13->37\n\t// comment\n0000000000AF0206:37->37\n";
+
+        let f1337path = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(content), &options).unwrap();
+
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+
+        // The default prefixes are replaced, not extended.
+        let wrong_format = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(">test.exe\n# comment\n"), &options).unwrap_err();
+        assert_eq!(wrong_format, PatchFileError::WrongFormat);
+    }
+
+
+    #[test]
+    fn test_validate() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        // Same address with the same new value is not a conflict.
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+
+        assert_eq!(f1337path.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_empty_filename() {
+        let mut f1337path = F1337Patch::new(" ".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+
+        assert_eq!(
+            f1337path.validate(),
+            Err(PatchFileError::InvalidHeader("header does not contain a target file name".to_string())),
+        );
+    }
+
+    #[test]
+    fn test_validate_conflicting_patch() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x13));
+
+        assert_eq!(f1337path.validate(), Err(PatchFileError::ConflictingPatch(0xAF0206)));
+    }
+
+    #[test]
+    fn test_validate_max_address() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xFFFFFFFFFFFFFFFF, 0x37, 0x37));
+
+        // The last addressable byte is a valid single-byte patch.
+        assert_eq!(f1337path.validate(), Ok(()));
+    }
+
+
+    #[test]
+    fn test_sample() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        for address in 0..10 {
+            f1337path.add_patch(HexPatch::new(address, 0x13, 0x37));
+        }
+
+        let sample = f1337path.sample(2);
+
+        assert_eq!(sample.target_filename, "test.exe");
+        assert_eq!(sample.patches, vec![
+            HexPatch::new(0, 0x13, 0x37),
+            HexPatch::new(5, 0x13, 0x37),
+        ]);
+        assert_eq!(f1337path.sample(20).patches, f1337path.patches);
+        assert_eq!(f1337path.sample(0).patches, vec![]);
+    }
+
+
+    #[test]
+    fn test_rva_round_trip() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0x140001000, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x140000000, 0x37, 0x37));
+
+        f1337path.to_rva(0x140000000).unwrap();
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0x1000, 0x13, 0x37),
+            HexPatch::new(0x0, 0x37, 0x37),
+        ]);
+
+        f1337path.to_absolute(0x140000000).unwrap();
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0x140001000, 0x13, 0x37),
+            HexPatch::new(0x140000000, 0x37, 0x37),
+        ]);
+    }
+
+    #[test]
+    fn test_rva_overflow() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0x140001000, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x1000, 0x37, 0x37));
+
+        assert_eq!(f1337path.to_rva(0x140000000), Err(PatchFileError::AddressOverflow(0x1000)));
+        // Nothing is converted on error.
+        assert_eq!(f1337path.patches[0].target_address, 0x140001000);
+
+        assert_eq!(f1337path.to_absolute(u64::MAX - 0x1000), Err(PatchFileError::AddressOverflow(0x140001000)));
+        assert_eq!(f1337path.patches[1].target_address, 0x1000);
+    }
+
+
+    #[test]
+    fn test_f1337patchset_list_targets() {
+        let mut dummy_file = tempfile().unwrap();
+
+        writeln!(dummy_file, ">game.exe").unwrap();
+        writeln!(dummy_file, "0000000000AF0200:13->37").unwrap();
+        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+        writeln!(dummy_file, ">engine.dll\r").unwrap();
+        writeln!(dummy_file, "not even a patch line").unwrap();
+        dummy_file.seek(io::SeekFrom::Start(0)).unwrap();
+
+        let targets = F1337PatchSet::list_targets(&mut io::BufReader::new(&dummy_file)).unwrap();
+
+        assert_eq!(targets, vec!["game.exe", "engine.dll"]);
+    }
+
+
+    /// A target failing to write at a given address, used to simulate a failure in the middle of an apply.
+    struct FailingTarget {
+        inner: io::Cursor<Vec<u8>>,
+        failing_address: u64,
+    }
+
+    impl Read for FailingTarget {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl Write for FailingTarget {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.inner.position() == self.failing_address {
+                return Err(io::Error::other("simulated write failure"));
+            }
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Seek for FailingTarget {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_apply_to_transactional_rollback() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = FailingTarget { inner: io::Cursor::new(vec![0x13, 0x74, 0x05, 0x00]), failing_address: 0x02 };
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0x02, 0x05, 0x90));
+
+        let error = f1337path.apply_to_transactional(&mut target).unwrap_err();
+
+        assert_eq!(error, PatchFileError::ReadError(io::Error::other("")));
+        assert_eq!(target.inner.into_inner(), vec![0x13, 0x74, 0x05, 0x00]);
+    }
+
+    #[test]
+    fn test_apply_to_transactional() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = FailingTarget { inner: io::Cursor::new(vec![0x13, 0x74, 0x05, 0x00]), failing_address: 0x03 };
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0x02, 0x05, 0x90));
+
+        assert_eq!(f1337path.apply_to_transactional(&mut target).unwrap(), 3);
+        assert_eq!(target.inner.into_inner(), vec![0x37, 0xEB, 0x90, 0x00]);
+    }
+
+
+    #[test]
+    fn test_old_signature() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0210, 0x05, 0x90));
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+
+        let signature = f1337path.old_signature();
+
+        assert!(signature.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert_eq!(signature, vec![(0xAF0200, 0x13), (0xAF0206, 0x37), (0xAF0210, 0x05)]);
+    }
+
+
+    #[test]
+    fn test_patches_per_word() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0x10, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x08, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0x17, 0x05, 0x90));
+
+        let words = f1337path.patches_per_word();
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[&1], vec![1]);
+        assert_eq!(words[&2], vec![0, 2]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_lines() {
+        let lines = vec![
+            "0000000000AF0200:13->37".to_string(),
+            "# comment".to_string(),
+            "0000000000AF0206:37->37".to_string(),
+        ];
+
+        let f1337path = F1337Patch::from_lines("test.exe".to_string(), lines).unwrap();
+
+        assert_eq!(f1337path.target_filename, "test.exe");
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+
+        let wrong_format = F1337Patch::from_lines("test.exe".to_string(), vec!["0000000000AF0200:13->3".to_string()]).unwrap_err();
+        assert_eq!(wrong_format, PatchFileError::WrongFormat);
+    }
+
+
+    #[test]
+    fn test_is_doubled() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        assert!(!f1337path.is_doubled());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+
+        assert!(!f1337path.is_doubled());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+
+        assert!(f1337path.is_doubled());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+
+        assert!(!f1337path.is_doubled());
+    }
+
+
+    #[test]
+    fn test_to_string_case() {
+        let mut f1337path = F1337Patch::new("Test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0xAF0200, 0x13, 0x3A));
+        f1337path.add_patch(HexPatch::new(0xAF020F, 0xEB, 0x74));
+
+        let upper = f1337path.to_string_case(HexCase::Upper);
+        let lower = f1337path.to_string_case(HexCase::Lower);
+
+        assert_eq!(upper, ">Test.exe\n0000000000AF0200:13->3A\n0000000000AF020F:EB->74\n");
+        assert_eq!(upper, f1337path.to_string());
+        assert_eq!(lower, ">Test.exe\n0000000000af0200:13->3a\n0000000000af020f:eb->74\n");
+
+        for serialized in [upper, lower] {
+            let parsed = F1337Patch::from_bufreader(&mut io::Cursor::new(serialized)).unwrap();
+
+            assert_eq!(parsed.target_filename, f1337path.target_filename);
+            assert_eq!(parsed.patches, f1337path.patches);
+        }
+    }
+
+
+    #[test]
+    fn test_verify_original() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x02, 0x74, 0xEB));
+
+        assert!(f1337path.verify_original(&mut io::Cursor::new(vec![0x13, 0x00, 0x74])).unwrap());
+        assert!(!f1337path.verify_original(&mut io::Cursor::new(vec![0x13, 0x00, 0xEB])).unwrap());
+        assert!(!f1337path.verify_original(&mut io::Cursor::new(vec![0x13, 0x00])).unwrap());
+    }
+
+    #[test]
+    fn test_verify_original_preserving() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut dummy_file = tempfile().unwrap();
+
+        f1337path.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x02, 0x74, 0xEB));
+        dummy_file.write_all(&[0x13, 0x00, 0x74, 0x00]).unwrap();
+        dummy_file.seek(io::SeekFrom::Start(1)).unwrap();
+
+        assert!(f1337path.verify_original_preserving(&mut dummy_file).unwrap());
+        assert_eq!(dummy_file.stream_position().unwrap(), 1);
+
+        dummy_file.seek(io::SeekFrom::Start(3)).unwrap();
+        f1337path.add_patch(HexPatch::new(0x03, 0x13, 0x37));
+
+        assert!(!f1337path.verify_original_preserving(&mut dummy_file).unwrap());
+        assert_eq!(dummy_file.stream_position().unwrap(), 3);
+    }
+
+
+    #[test]
+    fn test_nearest_to() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+
+        assert_eq!(f1337path.nearest_to(0x180), None);
+
+        f1337path.add_patch(HexPatch::new(0x200, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x100, 0x74, 0xEB));
+
+        assert_eq!(f1337path.nearest_to(0x180), Some(&HexPatch::new(0x100, 0x74, 0xEB)));
+        assert_eq!(f1337path.nearest_to(0x181), Some(&HexPatch::new(0x200, 0x13, 0x37)));
+        assert_eq!(f1337path.nearest_to(0x0), Some(&HexPatch::new(0x100, 0x74, 0xEB)));
+        assert_eq!(f1337path.nearest_to(u64::MAX), Some(&HexPatch::new(0x200, 0x13, 0x37)));
+    }
+
+
+    #[test]
+    fn test_range_patch_verify() {
+        let patch = RangePatch::new(0x01, vec![0x74, 0x05, 0x13], vec![0x90, 0x90, 0x90]);
+
+        assert!(patch.verify(&mut io::Cursor::new(vec![0x00, 0x74, 0x05, 0x13])).unwrap());
+        assert!(!patch.verify(&mut io::Cursor::new(vec![0x00, 0x74, 0x05, 0x14])).unwrap());
+    }
+
+    #[test]
+    fn test_range_patch_verify_past_eof() {
+        let patch = RangePatch::new(0x01, vec![0x74, 0x05, 0x13], vec![0x90, 0x90, 0x90]);
+
+        // The bytes present match, but the range is not complete.
+        let out_of_bounds = patch.verify(&mut io::Cursor::new(vec![0x00, 0x74, 0x05])).unwrap_err();
+        assert_eq!(out_of_bounds, PatchFileError::OutOfBounds(0x01));
+
+        let out_of_bounds = patch.verify(&mut io::Cursor::new(vec![])).unwrap_err();
+        assert_eq!(out_of_bounds, PatchFileError::OutOfBounds(0x01));
+    }
+
+    #[test]
+    #[should_panic(expected = "old and new values of a range patch must have the same length")]
+    fn test_range_patch_new_length_mismatch() {
+        RangePatch::new(0x01, vec![0x74, 0x05], vec![0x90]);
+    }
+
+    #[test]
+    fn test_range_patch_changed_byte_count() {
+        let patch = RangePatch::new(0x01, vec![0x74, 0x05, 0x13, 0x37], vec![0x90, 0x05, 0x13, 0x90]);
+        assert_eq!(patch.changed_byte_count(), 2);
+
+        let patch = RangePatch::new(0x01, vec![0x74, 0x05], vec![0x74, 0x05]);
+        assert_eq!(patch.changed_byte_count(), 0);
+        assert_eq!(RangePatch::new(0x01, vec![], vec![]).changed_byte_count(), 0);
+    }
+
+
+    #[test]
+    fn test_f1337patchset_patches_for() {
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut game = F1337Patch::new("game.exe".to_string());
+        let mut engine = F1337Patch::new("engine.dll".to_string());
+
+        game.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        engine.add_patch(HexPatch::new(0x1000, 0x74, 0xEB));
+        engine.add_patch(HexPatch::new(0x1002, 0x05, 0x90));
+        f1337patchset.add_file(game);
+        f1337patchset.add_file(engine);
+
+        assert_eq!(f1337patchset.patches_for("engine.dll"), Some([
+            HexPatch::new(0x1000, 0x74, 0xEB),
+            HexPatch::new(0x1002, 0x05, 0x90),
+        ].as_slice()));
+        assert_eq!(f1337patchset.patches_for("other.dll"), None);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_os() {
+        let mut dummy_file = tempfile().unwrap();
+
+        dummy_file.write_all(b">g\xFFm\xC3.exe\r\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n").unwrap();
+
+        let (f1337path, filename) = F1337Patch::from_bufreader_os(&mut io::BufReader::new(&dummy_file)).unwrap();
+
+        assert_eq!(filename, b"g\xFFm\xC3.exe");
+        assert_eq!(f1337path.target_filename, "g\u{FFFD}m\u{FFFD}.exe");
+        assert_eq!(f1337path.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x37, 0x37),
+        ]);
+
+        // The UTF-8 parser can't read that header.
+        assert!(F1337Patch::from_bufreader(&mut io::BufReader::new(&dummy_file)).is_err());
+    }
+
+
+    #[test]
+    fn test_edit_script() {
+        let mut current = F1337Patch::new("test.exe".to_string());
+        let mut desired = F1337Patch::new("test.exe".to_string());
+
+        current.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        current.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        desired.add_patch(HexPatch::new(0xAF0200, 0x13, 0x38));
+        desired.add_patch(HexPatch::new(0xAF0206, 0x37, 0x37));
+        desired.add_patch(HexPatch::new(0xAF0204, 0x74, 0xEB));
+
+        assert_eq!(current.edit_script(&desired), vec![
+            EditOp::Modify {
+                address: 0xAF0200,
+                from: HexPatch::new(0xAF0200, 0x13, 0x37),
+                to: HexPatch::new(0xAF0200, 0x13, 0x38),
+            },
+            EditOp::Add(HexPatch::new(0xAF0204, 0x74, 0xEB)),
+        ]);
+        assert_eq!(desired.edit_script(&current), vec![
+            EditOp::Modify {
+                address: 0xAF0200,
+                from: HexPatch::new(0xAF0200, 0x13, 0x38),
+                to: HexPatch::new(0xAF0200, 0x13, 0x37),
+            },
+            EditOp::Remove(HexPatch::new(0xAF0204, 0x74, 0xEB)),
+        ]);
+        assert_eq!(current.edit_script(&current), vec![]);
+    }
+
+
+    #[test]
+    fn test_apply_cost_estimate() {
+        let mut spread = F1337Patch::new("test.exe".to_string());
+        let mut clustered = F1337Patch::new("test.exe".to_string());
+
+        for index in 0..4 {
+            spread.add_patch(HexPatch::new(index * 0x1000, 0x13, 0x37));
+            clustered.add_patch(HexPatch::new(0xAF0200 + index * 2, 0x13, 0x37));
+        }
+
+        let spread_cost = spread.apply_cost_estimate(0x100);
+        let clustered_cost = clustered.apply_cost_estimate(0x100);
+
+        assert_eq!(spread_cost, ApplyCost { seeks: 8, read_bytes: 4, write_bytes: 4 });
+        assert_eq!(clustered_cost, ApplyCost { seeks: 2, read_bytes: 7, write_bytes: 7 });
+        assert!(clustered_cost.seeks < spread_cost.seeks);
+        assert_eq!(F1337Patch::new("test.exe".to_string()).apply_cost_estimate(0x100).seeks, 0);
+    }
+
+
+    #[test]
+    fn test_context_preview() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new((0x00..0x10).collect::<Vec<u8>>());
+
+        f1337path.add_patch(HexPatch::new(0x08, 0x08, 0x37));
+        f1337path.add_patch(HexPatch::new(0x01, 0x01, 0x37));
+        f1337path.add_patch(HexPatch::new(0x0F, 0x0F, 0x37));
+
+        let previews = f1337path.context_preview(&mut target, 3).unwrap();
+
+        assert_eq!(previews, vec![
+            BytePreview { target_address: 0x08, before: vec![0x05, 0x06, 0x07], after: vec![0x09, 0x0A, 0x0B], old: 0x08, new: 0x37 },
+            BytePreview { target_address: 0x01, before: vec![0x00], after: vec![0x02, 0x03, 0x04], old: 0x01, new: 0x37 },
+            BytePreview { target_address: 0x0F, before: vec![0x0C, 0x0D, 0x0E], after: vec![], old: 0x0F, new: 0x37 },
+        ]);
+    }
+
+
+    #[test]
+    fn test_assign_regions() {
+        let mut f1337path = F1337Patch::new("test.exe".to_string());
+        let regions = [
+            (0x1000, 0x1100, "check_license".to_string()),
+            (0x2000, 0x2010, "show_nag".to_string()),
+            (0x3000, 0x3010, "unused".to_string()),
+        ];
+
+        f1337path.add_patch(HexPatch::new(0x2000, 0x74, 0xEB));
+        f1337path.add_patch(HexPatch::new(0x1004, 0x13, 0x37));
+        f1337path.add_patch(HexPatch::new(0x2010, 0x05, 0x90));
+        f1337path.add_patch(HexPatch::new(0x10FF, 0x37, 0x37));
+
+        let assigned = f1337path.assign_regions(&regions);
+
+        assert_eq!(assigned.len(), 4);
+        assert_eq!(assigned["check_license"], vec![1, 3]);
+        assert_eq!(assigned["show_nag"], vec![0]);
+        assert_eq!(assigned["unused"], vec![]);
+        assert_eq!(assigned[""], vec![2]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_limited() {
+        let mut dummy_file = tempfile().unwrap();
+
+        writeln!(dummy_file, ">test.exe").unwrap();
+        for address in 0..5 {
+            writeln!(dummy_file, "{}", HexPatch::new(address, 0x13, 0x37)).unwrap();
+        }
+
+        let too_many_patches = F1337Patch::from_bufreader_limited(&mut io::BufReader::new(&dummy_file), 3).unwrap_err();
+        assert_eq!(too_many_patches, PatchFileError::TooManyPatches(3));
+
+        let f1337path = F1337Patch::from_bufreader_limited(&mut io::BufReader::new(&dummy_file), 5).unwrap();
+        assert_eq!(f1337path.patches.len(), 5);
+    }
+
+
+    #[test]
+    fn test_f1337patch_is_consistent() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        assert!(f1337patch.is_consistent());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x38));
+        assert!(!f1337patch.is_consistent());
+    }
+
+
+    #[test]
+    fn test_f1337patch_display_preserves_order() {
+        let content = concat!(
+            ">test.exe\n",
+            "0000000000AF0206:37->13\n",
+            "0000000000000010:00->90\n",
+            "0000000000AF0200:13->37\n",
+            "0000000000000001:01->02\n",
+        );
+        let f1337patch = F1337Patch::from_bufreader(&mut io::Cursor::new(content)).unwrap();
+
+        assert_eq!(f1337patch.to_string(), content);
+    }
+
+
+    #[test]
+    fn test_f1337patch_count_jump_patches() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0210, 0x0F, X86_NOP));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0211, 0x84, 0xE9));
+        assert_eq!(f1337patch.count_jump_patches(), 2);
+    }
+
+
+    #[test]
+    fn test_f1337patch_try_add_patch() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.try_add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37)).unwrap();
+        let conflicting_patch = f1337patch.try_add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x38)).unwrap_err();
+        assert_eq!(conflicting_patch, PatchFileError::ConflictingPatch(0x0000000000AF0200));
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0x0000000000AF0200, 0x13, 0x37)]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_resync_old_from() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000000, 0x00, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000002, 0x00, 0x13));
+        f1337patch.resync_old_from(&mut io::Cursor::new(vec![0x13, 0x00, 0x37])).unwrap();
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000000000, 0x13, 0x37),
+            HexPatch::new(0x0000000000000002, 0x37, 0x13),
+        ]);
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000003, 0x00, 0x13));
+        let out_of_bounds = f1337patch.resync_old_from(&mut io::Cursor::new(vec![0x00; 3])).unwrap_err();
+        assert_eq!(out_of_bounds, PatchFileError::OutOfBounds(0x0000000000000003));
+        assert_eq!(f1337patch.patches[0].old, 0x13);
+    }
+
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_f1337patch_from_zip_entry() {
+        let archive = tempfile::NamedTempFile::new().unwrap();
+        let mut zip_writer = zip::ZipWriter::new(archive.reopen().unwrap());
+
+        zip_writer.start_file("test.1337", zip::write::SimpleFileOptions::default()).unwrap();
+        zip_writer.write_all(b">test.exe\n0000000000AF0200:13->37\n").unwrap();
+        zip_writer.finish().unwrap();
+
+        let f1337patch = F1337Patch::from_zip_entry(archive.path(), "test.1337").unwrap();
+        assert_eq!(f1337patch.target_filename, "test.exe");
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0x0000000000AF0200, 0x13, 0x37)]);
+
+        assert!(matches!(F1337Patch::from_zip_entry(archive.path(), "missing.1337"), Err(PatchFileError::ReadError(_))));
+    }
+
+
+    #[test]
+    fn test_f1337patch_describe() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert_eq!(f1337patch.describe(), "test.exe: 0 patches (0 effective, 0 no-ops)");
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+        assert_eq!(f1337patch.describe(), "test.exe: 1 patch (1 effective, 0 no-ops), addresses 0xAF0206..0xAF0206");
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0210, 0x37, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0210, 0x13, 0x13));
+        assert_eq!(f1337patch.describe(), "test.exe: 3 patches (1 effective, 2 no-ops), addresses 0xAF0206..0xAF0210");
+
+        f1337patch.patches.truncate(1);
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0210, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0202, 0x00, 0x01));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0204, 0x37, 0x37));
+        assert_eq!(f1337patch.describe(), "test.exe: 5 patches (4 effective, 1 no-op), addresses 0xAF0200..0xAF0210");
+    }
+
+
+    #[test]
+    fn test_f1337patch_require_unique_addresses() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patch.require_unique_addresses().unwrap();
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x38));
+        assert_eq!(f1337patch.require_unique_addresses(), Err(PatchFileError::DuplicateAddress(0x0000000000AF0206)));
+    }
+
+
+    #[test]
+    fn test_check_patch_line_format_multibyte() {
+        assert_eq!(F1337Patch::check_patch_line_format("0000000000AF0200é3->37"), Err(PatchFileError::WrongFormat));
+        assert_eq!(F1337Patch::check_patch_line_format("00000000000AF0é:13->37"), Err(PatchFileError::WrongFormat));
+    }
+
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_f1337patch_apply_if_hash() {
+        use sha2::{Digest, Sha256};
+
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let data = vec![0x00, 0x13];
+        let expected_sha256: [u8; 32] = Sha256::digest(&data).into();
+
+        f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+
+        let mut wrong_version = io::Cursor::new(vec![0x01, 0x13]);
+        let hash_mismatch = f1337patch.apply_if_hash(&mut wrong_version, expected_sha256).unwrap_err();
+        assert!(matches!(hash_mismatch, PatchFileError::HashMismatch { expected, .. } if expected == expected_sha256));
+        assert_eq!(wrong_version.into_inner(), vec![0x01, 0x13]);
+
+        let mut target = io::Cursor::new(data);
+        assert_eq!(f1337patch.apply_if_hash(&mut target, expected_sha256).unwrap(), 1);
+        assert_eq!(target.into_inner(), vec![0x00, 0x37]);
+    }
+
+    #[cfg(feature = "fs2")]
+    #[test]
+    fn test_f1337patch_apply_to_path_locked() {
+        use fs2::FileExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.exe");
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        std::fs::write(&path, [0x00, 0x13]).unwrap();
+        f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+
+        assert_eq!(f1337patch.apply_to_path_locked(&path).unwrap(), 1);
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0x00, 0x37]);
+
+        // The lock is released after applying, even when it fails.
+        let mismatch = f1337patch.apply_to_path_locked(&path).unwrap_err();
+        assert_eq!(mismatch, PatchFileError::OldValueMismatch { address: 0x01, expected: 0x13, found: 0x37 });
+
+        let file = std::fs::File::open(&path).unwrap();
+        FileExt::try_lock_exclusive(&file).unwrap();
+        FileExt::unlock(&file).unwrap();
+    }
+
+
+    #[test]
+    fn test_f1337patch_to_sparse_ops() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert!(f1337patch.to_sparse_ops().is_empty());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x00, 0x01));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0201, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0202, 0x00, 0x01));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0202, 0x00, 0x02));
+        assert_eq!(f1337patch.to_sparse_ops(), vec![
+            (0x0000000000AF0200, vec![0x37, 0x13, 0x02]),
+            (0x0000000000AF0206, vec![0x01]),
+        ]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_upgrade_to() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        assert_eq!(f1337patch.format_version(), 1);
+
+        f1337patch.upgrade_to(LATEST_FORMAT_VERSION).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0x0000000000AF0200, 0x13, 0x37)]);
+
+        assert_eq!(f1337patch.upgrade_to(0), Err(PatchFileError::UnsupportedVersion(0)));
+        assert_eq!(f1337patch.upgrade_to(LATEST_FORMAT_VERSION + 1), Err(PatchFileError::UnsupportedVersion(LATEST_FORMAT_VERSION + 1)));
+        assert_eq!(f1337patch.format_version(), 1);
+    }
+
+    #[test]
+    fn test_f1337patch_derived_sets_keep_format_version() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x74, 0xEB));
+        // Only version 1 exists so far: set another version directly to tell it apart from the default one.
+        f1337patch.format_version = 2;
+
+        assert_eq!(f1337patch.without_redundant(&F1337Patch::new("test.exe".to_string())).format_version(), 2);
+        let (matching, others) = f1337patch.partition_by(|patch| patch.old == 0x13);
+        assert_eq!((matching.format_version(), others.format_version()), (2, 2));
+        assert_eq!(f1337patch.sample(1).format_version(), 2);
+    }
+
+
+    #[test]
+    fn test_f1337patch_preflight() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x03, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x04, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x05, 0x13, 0x37));
+
+        let preflight = f1337patch.preflight(&mut io::Cursor::new(vec![0x13, 0x13, 0x37, 0x00])).unwrap();
+        assert_eq!(preflight, Preflight {
+            will_apply: 2,
+            already_applied: 1,
+            mismatched: 1,
+            out_of_bounds: 2,
+        });
+    }
+
+
+    #[test]
+    fn test_f1337patchset_apply_to_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut game = F1337Patch::new("game.exe".to_string());
+        let mut engine = F1337Patch::new("engine.dll".to_string());
+        let mut missing = F1337Patch::new("missing.dll".to_string());
+
+        std::fs::write(dir.path().join("game.exe"), [0x13, 0x37, 0x00]).unwrap();
+        std::fs::write(dir.path().join("engine.dll"), [0x00, 0x13]).unwrap();
+        game.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        game.add_patch(HexPatch::new(0x01, 0x37, 0x37));
+        game.add_patch(HexPatch::new(0x02, 0x00, 0x90));
+        engine.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+        missing.add_patch(HexPatch::new(0x00, 0x00, 0x90));
+        f1337patchset.add_file(game);
+        f1337patchset.add_file(engine);
+        f1337patchset.add_file(missing);
+
+        let changed = f1337patchset.apply_to_dir(dir.path()).unwrap();
+        assert_eq!(changed, HashMap::from([("game.exe".to_string(), 2), ("engine.dll".to_string(), 1)]));
+        assert!(!changed.contains_key("missing.dll"));
+        assert_eq!(std::fs::read(dir.path().join("game.exe")).unwrap(), vec![0x37, 0x37, 0x90]);
+        assert_eq!(std::fs::read(dir.path().join("engine.dll")).unwrap(), vec![0x00, 0x37]);
+        assert!(!dir.path().join("missing.dll").exists());
+    }
+
+
+    #[test]
+    fn test_f1337patch_is_valid_target() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
+
+        assert!(f1337patch.is_valid_target(&mut io::Cursor::new(vec![0x13, 0x00, 0x37]), "test.exe").unwrap());
+        assert!(!f1337patch.is_valid_target(&mut io::Cursor::new(vec![0x13, 0x00]), "test.exe").unwrap());
+        assert!(!f1337patch.is_valid_target(&mut io::Cursor::new(vec![0x13, 0x00, 0x38]), "test.exe").unwrap());
+        assert!(!f1337patch.is_valid_target(&mut io::Cursor::new(vec![0x13, 0x00, 0x37]), "other.exe").unwrap());
+    }
+
+
+    #[test]
+    fn test_f1337patch_to_string_effective() {
+        let f1337patch = F1337Patch::from_bufreader(
+            &mut io::Cursor::new(">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n")
+        ).unwrap();
+        let serialized = f1337patch.to_string_effective();
+
+        assert!(!serialized.contains("37->37"));
+
+        let effective = F1337Patch::from_bufreader(&mut io::Cursor::new(serialized)).unwrap();
+        assert_eq!(effective.target_filename, "test.exe");
+        assert_eq!(effective.patches, vec![HexPatch::new(0x0000000000AF0200, 0x13, 0x37)]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_find_header() {
+        let content = concat!(
+            "Exported by x64dbg\n",
+            "Module: test.exe\n",
+            ">test.exe\n",
+            "0000000000AF0200:13->37\n",
+            "0000000000AF0206:37->37\n",
+        );
+
+        let f1337patch = F1337Patch::from_bufreader_find_header(&mut content.as_bytes(), 3).unwrap();
+        assert_eq!(f1337patch.target_filename, "test.exe");
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000AF0200, 0x13, 0x37),
+            HexPatch::new(0x0000000000AF0206, 0x37, 0x37),
+        ]);
+
+        let missing_header = F1337Patch::from_bufreader_find_header(&mut content.as_bytes(), 2).unwrap_err();
+        assert!(matches!(missing_header, PatchFileError::InvalidHeader(_)));
+    }
+
+
+    #[test]
+    fn test_f1337patch_runs() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert!(f1337patch.runs().is_empty());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000102, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000100, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000010, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000101, 0x00, 0x90));
+
+        // Runs are found in address order, without reordering the patches.
+        assert_eq!(f1337patch.runs(), vec![0..1, 1..4, 4..5]);
+        assert_eq!(f1337patch.patches[0].target_address, 0x0000000000000102);
+
+        // Once sorted, the ranges index the patches themselves.
+        f1337patch.sort_by_address();
+        assert_eq!(f1337patch.runs(), vec![0..1, 1..4, 4..5]);
+        let run: Vec<u64> = f1337patch.patches[1..4].iter().map(|patch| patch.target_address).collect();
+        assert_eq!(run, vec![0x0000000000000100, 0x0000000000000101, 0x0000000000000102]);
+    }
+
+    #[test]
+    fn test_f1337patchset_apply_to_dir_rejects_escaping_names() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("game");
+        let outside = root.path().join("x");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("game.exe"), [0x13]).unwrap();
+        std::fs::write(&outside, [0x13]).unwrap();
+
+        let absolute = outside.to_str().unwrap().to_string();
+        for name in ["../x", absolute.as_str(), "", "sub/../../x"] {
+            let mut f1337patchset = F1337PatchSet::new();
+            let mut game = F1337Patch::new("game.exe".to_string());
+            let mut escaping = F1337Patch::new(name.to_string());
+
+            game.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+            escaping.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+            f1337patchset.add_file(game);
+            f1337patchset.add_file(escaping);
+
+            for result in [f1337patchset.apply_to_dir(&dir), f1337patchset.apply_to_dir_atomic(&dir)] {
+                let invalid_header = result.unwrap_err();
+                assert_eq!(invalid_header, PatchFileError::InvalidHeader(format!("target file name is not a relative path inside the directory: {}", name)));
+            }
+            // Nothing is patched, neither inside nor outside of the directory.
+            assert_eq!(std::fs::read(dir.join("game.exe")).unwrap(), vec![0x13]);
+            assert_eq!(std::fs::read(&outside).unwrap(), vec![0x13]);
+        }
+
+        // Plain names in sub directories are accepted.
+        std::fs::create_dir(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin").join("engine.dll"), [0x13]).unwrap();
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut engine = F1337Patch::new("bin/engine.dll".to_string());
+        engine.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patchset.add_file(engine);
+
+        assert_eq!(f1337patchset.apply_to_dir_atomic(&dir).unwrap(), HashMap::from([("bin/engine.dll".to_string(), 1)]));
+        assert_eq!(std::fs::read(dir.join("bin").join("engine.dll")).unwrap(), vec![0x37]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_restrict_to_window() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000FFF, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001FFF, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000002000, 0x00, 0x90));
+
+        assert_eq!(f1337patch.restrict_to_window(0x1000..0x2000), 2);
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000001000, 0x13, 0x37),
+            HexPatch::new(0x0000000000001FFF, 0x37, 0x13),
+        ]);
+        assert_eq!(f1337patch.restrict_to_window(0x1000..0x2000), 0);
+    }
+
+    #[test]
+    fn test_f1337patch_reject_outside_window() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001FFF, 0x37, 0x13));
+        f1337patch.reject_outside_window(0x1000..0x2000).unwrap();
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000002000, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000FFF, 0x00, 0x90));
+        assert_eq!(f1337patch.reject_outside_window(0x1000..0x2000), Err(PatchFileError::OutsideWindow(0x2000)));
+        assert_eq!(f1337patch.patches.len(), 4);
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_lossy_utf8() {
+        let content = b">test\xFF.exe\n# Patched by caf\xE9 team\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n";
+
+        assert!(F1337Patch::from_bufreader(&mut io::Cursor::new(content.to_vec())).is_err());
+
+        let f1337patch = F1337Patch::from_bufreader_lossy_utf8(&mut io::Cursor::new(content.to_vec())).unwrap();
+        assert_eq!(f1337patch.target_filename, "test\u{FFFD}.exe");
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000AF0200, 0x13, 0x37),
+            HexPatch::new(0x0000000000AF0206, 0x37, 0x37),
+        ]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_by_new_value_frequency() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert!(f1337patch.by_new_value_frequency().is_empty());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x10, 0x13, 0x37));
+        f1337patch.nop_range(0x20, &[0x00, 0x01, 0x02]).unwrap();
+        f1337patch.add_patch(HexPatch::new(0x30, 0x75, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x40, 0x00, 0x01));
+        assert_eq!(f1337patch.by_new_value_frequency(), vec![(X86_NOP, 3), (0xEB, 2), (0x01, 1), (0x37, 1)]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_self_check() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+        assert!(f1337patch.self_check());
+
+        f1337patch.target_filename = "my game.exe".to_string();
+        assert!(f1337patch.self_check());
+
+        f1337patch.target_filename = "test.exe ".to_string();
+        assert!(!f1337patch.self_check());
+    }
+
+    #[test]
+    fn test_f1337patch_from_str_content() {
+        let content = ">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n";
+        let f1337patch = F1337Patch::from_str_content(content).unwrap();
+
+        assert_eq!(f1337patch, F1337Patch::from_bufreader(&mut io::Cursor::new(content)).unwrap());
+        assert_eq!(f1337patch.to_string(), content);
+    }
+
+    #[test]
+    fn test_fill_patch_expand_address_overflow() {
+        assert_eq!(FillPatch::new(u64::MAX, Some(0x00), X86_NOP, 2).expand(), None);
+        assert_eq!(FillPatch::new(u64::MAX, Some(0x00), X86_NOP, 1).expand(), Some(vec![HexPatch::new(u64::MAX, 0x00, X86_NOP)]));
+        assert_eq!(FillPatch::new(u64::MAX, Some(0x00), X86_NOP, 0).expand(), Some(vec![]));
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_fill_line() {
+        let content = ">test.exe\n0000000000000010:13->37\n0000000000AF0200:00->90*3\n";
+        let f1337patch = F1337Patch::from_str_content(content).unwrap();
+
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000000010, 0x13, 0x37),
+            HexPatch::new(0x0000000000AF0200, 0x00, X86_NOP),
+            HexPatch::new(0x0000000000AF0201, 0x00, X86_NOP),
+            HexPatch::new(0x0000000000AF0202, 0x00, X86_NOP),
+        ]);
+
+        // Every filled byte counts as a patch.
+        let options = ParseOptions { max_patches: Some(3), ..ParseOptions::default() };
+        let too_many = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(content), &options).unwrap_err();
+        assert_eq!(too_many, PatchFileError::TooManyPatches(3));
+
+        // A single patch cannot hold any old value.
+        let any_old = F1337Patch::from_str_content(">test.exe\n0000000000AF0200:*->90*16\n").unwrap_err();
+        assert_eq!(any_old, PatchFileError::WrongFormat);
+    }
+
+
+    #[test]
+    fn test_fill_patch_from_line() {
+        assert_eq!(FillPatch::from_line("0000000000AF0200:*->90*16").unwrap(), FillPatch::new(0x0000000000AF0200, None, X86_NOP, 16));
+        assert_eq!(FillPatch::from_line("0000000000AF0200:13->37*2").unwrap(), FillPatch::new(0x0000000000AF0200, Some(0x13), 0x37, 2));
+        assert_eq!(FillPatch::from_line("0000000000AF0200:13->37"), Err(PatchFileError::WrongFormat));
+        assert_eq!(FillPatch::from_line("0000000000AF0200:13->37*0"), Err(PatchFileError::WrongFormat));
+        assert_eq!(FillPatch::from_line("0000000000AF0200:**->37*1"), Err(PatchFileError::WrongFormat));
+        assert_eq!(FillPatch::from_line("FFFFFFFFFFFFFFFF:*->90*2"), Err(PatchFileError::AddressOverflow(u64::MAX)));
+
+        let patch = FillPatch::new(0x0000000000AF0200, None, X86_NOP, 16);
+        assert_eq!(FillPatch::from_line(&patch.to_string()).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_fill_patch_expand() {
+        let patch = FillPatch::from_line("0000000000AF0200:00->90*4").unwrap();
+
+        assert_eq!(patch.expand().unwrap(), vec![
+            HexPatch::new(0x0000000000AF0200, 0x00, X86_NOP),
+            HexPatch::new(0x0000000000AF0201, 0x00, X86_NOP),
+            HexPatch::new(0x0000000000AF0202, 0x00, X86_NOP),
+            HexPatch::new(0x0000000000AF0203, 0x00, X86_NOP),
+        ]);
+        assert_eq!(FillPatch::new(0x0000000000AF0200, None, X86_NOP, 4).expand(), None);
+    }
+
+
+    #[test]
+    fn test_f1337patch_confirm_applied() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x00, 0x37]);
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x38));
+        f1337patch.apply_to(&mut target).unwrap();
+        assert!(f1337patch.confirm_applied(&mut target).unwrap().is_empty());
+
+        let mut tampered = io::Cursor::new(vec![0x37, 0x00, 0x13]);
+        assert_eq!(f1337patch.confirm_applied(&mut tampered).unwrap(), vec![0x02]);
+
+        f1337patch.add_patch(HexPatch::new(0x03, 0x00, 0x90));
+        assert_eq!(f1337patch.confirm_applied(&mut target).unwrap(), vec![0x03]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_to_section_relative() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let sections = [
+            (".text".to_string(), 0x0000000000001000, 0x1000),
+            (".data".to_string(), 0x0000000000003000, 0x0200),
+        ];
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000003010, 0x00, 0x01));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001FFF, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x0000000000002000, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x37, 0x13));
+
+        let (relative, outside) = f1337patch.to_section_relative(&sections);
+        assert_eq!(relative, vec![
+            (".data".to_string(), 0x10, 0x00, 0x01),
+            (".text".to_string(), 0xFFF, 0x74, 0xEB),
+            (".text".to_string(), 0x000, 0x37, 0x13),
+        ]);
+        assert_eq!(outside, vec![HexPatch::new(0x0000000000002000, 0x13, 0x37)]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_apply_to_with_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("test.exe.journal");
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let original = vec![0x13, 0x00, 0x37];
+        let mut target = io::Cursor::new(original.clone());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, X86_NOP));
+        assert_eq!(f1337patch.apply_to_with_journal(&mut target, &journal_path).unwrap(), 3);
+        assert_eq!(target.get_ref(), &vec![0x37, 0x00, X86_NOP]);
+
+        let journal = std::fs::read(&journal_path).unwrap();
+        assert_eq!(&journal[..8], b"1337JRNL");
+        assert_eq!(journal[8..].len(), 3 * 9);
+
+        // Revert the entries in reverse order to restore the original bytes.
+        let mut patched = target.into_inner();
+        for entry in journal[8..].chunks(9).rev() {
+            let address = u64::from_le_bytes(entry[..8].try_into().unwrap());
+            patched[address as usize] = entry[8];
+        }
+        assert_eq!(patched, original);
+
+        let mut mismatching = io::Cursor::new(vec![0x00; 3]);
+        let journal_path = dir.path().join("mismatching.journal");
+        assert!(f1337patch.apply_to_with_journal(&mut mismatching, &journal_path).is_err());
+        assert!(!journal_path.exists());
+    }
+
+
+    #[test]
+    fn test_f1337patch_revert_from_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_path = dir.path().join("test.exe.journal");
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let original = vec![0x13, 0x00, 0x37];
+        let mut target = io::Cursor::new(original.clone());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, X86_NOP));
+        f1337patch.apply_to_with_journal(&mut target, &journal_path).unwrap();
+        assert_eq!(F1337Patch::revert_from_journal(&mut target, &journal_path).unwrap(), 3);
+        assert_eq!(target.get_ref(), &original);
+
+        let journal = std::fs::read(&journal_path).unwrap();
+        let truncated_path = dir.path().join("truncated.journal");
+        std::fs::write(&truncated_path, &journal[..journal.len() - 1]).unwrap();
+        assert_eq!(F1337Patch::revert_from_journal(&mut target, &truncated_path), Err(PatchFileError::InvalidJournal));
+
+        let wrong_magic_path = dir.path().join("wrong_magic.journal");
+        std::fs::write(&wrong_magic_path, b"1337").unwrap();
+        assert_eq!(F1337Patch::revert_from_journal(&mut target, &wrong_magic_path), Err(PatchFileError::InvalidJournal));
+        assert_eq!(target.into_inner(), original);
     }
 
+
     #[test]
-    fn test_f1337patch_from_filepatch() {
-        let mut dummy_file = tempfile().unwrap();
-        let f1337path: F1337Patch;
-        let dummy_patches: Vec<HexPatch>;
-        
-        writeln!(dummy_file, ">test.exe").unwrap();
-        writeln!(dummy_file, "0000000000AF0200:13->37").unwrap();
-        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+    fn test_f1337patch_likely_address_width() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
 
-        f1337path = F1337Patch::from_patchfile(&mut dummy_file).unwrap();
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x00000000FFFFFFFF, 0x37, 0x13));
+        assert_eq!(f1337patch.likely_address_width(), AddressWidth::Bits32);
 
-        assert_eq!(f1337path.target_filename, "test.exe");
-        assert_eq!(f1337path.patches.len(), 2);
+        f1337patch.add_patch(HexPatch::new(0x0000000140001000, 0x00, 0x90));
+        assert_eq!(f1337patch.likely_address_width(), AddressWidth::Bits64);
+    }
 
-        dummy_patches = vec![
-            HexPatch::new(0xAF0200, 0x13, 0x37),
-            HexPatch::new(0xAF0206, 0x37, 0x37),
+
+    #[test]
+    fn test_f1337patch_reversed() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x00]);
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x01, 0x00, X86_NOP));
+        assert_eq!(f1337patch.reversed().patches, vec![
+            HexPatch::new(0x01, X86_NOP, 0x00),
+            HexPatch::new(0x00, 0x37, 0x13),
+        ]);
+
+        f1337patch.apply_to(&mut target).unwrap();
+        f1337patch.reversed().apply_to(&mut target).unwrap();
+        assert_eq!(target.into_inner(), vec![0x13, 0x00]);
+    }
+
+    #[test]
+    fn test_f1337patch_write_reverse_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.undo.1337");
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+        f1337patch.write_reverse_to_file(&path).unwrap();
+
+        let reverse = F1337Patch::from_patchfile(&File::open(&path).unwrap()).unwrap();
+        assert_eq!(reverse.target_filename, "test.exe");
+        assert_eq!(reverse.patches, vec![
+            HexPatch::new(0x0000000000AF0206, 0x37, 0x37),
+            HexPatch::new(0x0000000000AF0200, 0x37, 0x13),
+        ]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_validate_with() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000003000, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001FFF, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000002000, 0x00, 0x90));
+
+        assert_eq!(f1337patch.validate_with(|patch| patch.target_address < 0x2000), vec![1, 3]);
+        assert!(f1337patch.validate_with(|_| true).is_empty());
+    }
+
+
+    #[test]
+    fn test_f1337patch_stream_apply() {
+        let content = concat!(
+            ">test.exe\n",
+            "# Skip the license check.\n",
+            "0000000000000000:13->37\n",
+            "0000000000000002:37->37\n",
+            "0000000000000001:00->90\n",
+        );
+        let mut target = io::Cursor::new(vec![0x13, 0x00, 0x37]);
+
+        assert_eq!(F1337Patch::stream_apply(&mut content.as_bytes(), &mut target).unwrap(), 3);
+        assert_eq!(target.get_ref(), &vec![0x37, X86_NOP, 0x37]);
+
+        let mut mismatching = io::Cursor::new(vec![0x13, 0x01, 0x37]);
+        let old_value_mismatch = F1337Patch::stream_apply(&mut content.as_bytes(), &mut mismatching).unwrap_err();
+        assert_eq!(old_value_mismatch, PatchFileError::OldValueMismatch { address: 0x01, expected: 0x00, found: 0x01 });
+        assert_eq!(mismatching.into_inner(), vec![0x37, 0x01, 0x37]);
+    }
+
+    #[test]
+    fn test_f1337patch_stream_apply_fill_line() {
+        let mut patch_reader = ">test.exe\n0000000000000001:00->90*2\n".as_bytes();
+        let mut target = io::Cursor::new(vec![0x13, 0x00, 0x00]);
+
+        assert_eq!(F1337Patch::stream_apply(&mut patch_reader, &mut target).unwrap(), 2);
+        assert_eq!(target.into_inner(), vec![0x13, X86_NOP, X86_NOP]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_stream_apply_stats() {
+        let content = concat!(
+            ">test.exe\n",
+            "0000000000000000:13->37\n",
+            "0000000000000001:00->90\n",
+            "0000000000000002:37->37\n",
+            "0000000000000003:13->37\n",
+            "0000000000000010:00->90\n",
+        );
+        let mut target = io::Cursor::new(vec![0x13, 0x00, 0x37, 0x00]);
+
+        let stats = F1337Patch::stream_apply_stats(&mut content.as_bytes(), &mut target).unwrap();
+        assert_eq!(stats, StreamStats {
+            applied: 2,
+            skipped_noop: 1,
+            mismatched: 2,
+        });
+        assert_eq!(target.into_inner(), vec![0x37, X86_NOP, 0x37, 0x00]);
+    }
+
+    #[test]
+    fn test_f1337patch_stream_apply_stats_fill_line() {
+        let mut patch_reader = ">test.exe\n0000000000000000:00->90*3\n".as_bytes();
+        let mut target = io::Cursor::new(vec![0x00, 0x13, 0x00]);
+
+        let stats = F1337Patch::stream_apply_stats(&mut patch_reader, &mut target).unwrap();
+        assert_eq!(stats, StreamStats { applied: 2, skipped_noop: 0, mismatched: 1 });
+        assert_eq!(target.into_inner(), vec![X86_NOP, 0x13, X86_NOP]);
+    }
+
+    #[test]
+    fn test_f1337patchset_write_split_colliding_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut slash = F1337Patch::new("a/b".to_string());
+        let mut colon = F1337Patch::new("a:b".to_string());
+
+        slash.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        colon.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patchset.add_file(slash);
+        f1337patchset.add_file(colon);
+
+        assert_eq!(
+            f1337patchset.write_split(dir.path()).unwrap_err(),
+            PatchFileError::InvalidHeader("target file names a/b and a:b are both written to a_b.1337".to_string())
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+
+    #[test]
+    fn test_f1337patchset_write_split() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut game = F1337Patch::new("game.exe".to_string());
+        let mut engine = F1337Patch::new("../engine.dll".to_string());
+
+        game.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        engine.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patchset.add_file(game);
+        f1337patchset.add_file(engine);
+
+        let paths = f1337patchset.write_split(dir.path()).unwrap();
+        assert_eq!(paths, vec![dir.path().join("game.exe.1337"), dir.path().join(".._engine.dll.1337")]);
+
+        let game = F1337Patch::from_patchfile(&File::open(&paths[0]).unwrap()).unwrap();
+        assert_eq!(game.target_filename, "game.exe");
+        assert_eq!(game.patches, vec![HexPatch::new(0x0000000000AF0200, 0x13, 0x37)]);
+
+        let engine = F1337Patch::from_patchfile(&File::open(&paths[1]).unwrap()).unwrap();
+        assert_eq!(engine.target_filename, "../engine.dll");
+        assert_eq!(engine.patches, vec![HexPatch::new(0x0000000000AF0206, 0x37, 0x13)]);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        assert_eq!(F1337PatchSet::sanitize_filename(".."), "_");
+        assert_eq!(F1337PatchSet::sanitize_filename("C:\\game\\test.exe"), "C__game_test.exe");
+    }
+
+
+    #[test]
+    fn test_address_too_long() {
+        let line = "00000000000000AF0200:13->37";
+
+        let too_long = F1337Patch::get_hex_patch_from_line(line).unwrap_err();
+        assert_eq!(too_long.kind(), &std::num::IntErrorKind::PosOverflow);
+        assert_eq!(
+            F1337Patch::from_bufreader(&mut io::Cursor::new(format!(">test.exe\n{}\n", line))).unwrap_err(),
+            PatchFileError::ConvertionError(too_long.clone())
+        );
+        assert_eq!(
+            F1337Patch::from_bufreader_with_options(
+                &mut io::Cursor::new(format!(">test.exe\n{}\n", line)),
+                &ParseOptions { lenient: true, ..ParseOptions::default() },
+            ).unwrap_err(),
+            PatchFileError::ConvertionError(too_long)
+        );
+        assert_eq!(F1337Patch::from_str_content(">test.exe\n0000000000AF0200:13->3\n").unwrap_err(), PatchFileError::WrongFormat);
+    }
+
+
+    #[test]
+    fn test_f1337patch_coverage_bitmap() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x03, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0F, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x10, 0x00, 0x90));
+
+        let bitmap = f1337patch.coverage_bitmap(16);
+        assert_eq!(bitmap.len(), 16);
+        let covered: Vec<usize> = bitmap.iter().enumerate().filter(|(_, covered)| **covered).map(|(index, _)| index).collect();
+        assert_eq!(covered, vec![0x03, 0x0F]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_split_by_applied() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x01, 0x00, X86_NOP));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x03, 0x00, X86_NOP));
+        f1337patch.add_patch(HexPatch::new(0x04, 0x00, X86_NOP));
+
+        let (applied, pending) = f1337patch.split_by_applied(&mut io::Cursor::new(vec![0x37, 0x00, 0x13, 0x00])).unwrap();
+        assert_eq!(applied.target_filename, "test.exe");
+        assert_eq!(applied.patches, vec![
+            HexPatch::new(0x00, 0x13, 0x37),
+            HexPatch::new(0x02, 0x37, 0x13),
+        ]);
+        assert_eq!(pending.target_filename, "test.exe");
+        assert_eq!(pending.patches, vec![
+            HexPatch::new(0x01, 0x00, X86_NOP),
+            HexPatch::new(0x03, 0x00, X86_NOP),
+            HexPatch::new(0x04, 0x00, X86_NOP),
+        ]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_dedup_all() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x90));
+        f1337patch.dedup_all();
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0x0000000000AF0206, 0x37, 0x13),
+            HexPatch::new(0x0000000000AF0200, 0x13, 0x37),
+            HexPatch::new(0x0000000000AF0206, 0x37, 0x90),
+        ]);
+    }
+
+    #[test]
+    fn test_f1337patch_format_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.1337");
+
+        std::fs::write(&path, concat!(
+            ">test.exe\r\n",
+            "# Skip the license check.\r\n",
+            "0000000000af0206 : 37 -> 13\r\n",
+            "0000000000AF0200:13->37\n",
+            "0000000000AF0206:37->13",
+        )).unwrap();
+        F1337Patch::format_file(&path, FormatOptions::default()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            ">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->13\n"
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let options = FormatOptions { sort: false, dedup: false, case: HexCase::Lower };
+        std::fs::write(&path, ">test.exe\n0000000000AF0206:37->13\n0000000000AF0200:13->37\n0000000000AF0200:13->37\n").unwrap();
+        F1337Patch::format_file(&path, options).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            ">test.exe\n0000000000af0206:37->13\n0000000000af0200:13->37\n0000000000af0200:13->37\n"
+        );
+    }
+
+
+    #[test]
+    fn test_f1337patch_count_in_ranges() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000FFF, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001000, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000001800, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000002000, 0x00, 0x90));
+
+        assert_eq!(f1337patch.count_in_ranges(&[0x1000..0x2000, 0x0800..0x1801, 0x3000..0x3000]), vec![2, 3, 0]);
+        assert!(f1337patch.count_in_ranges(&[]).is_empty());
+    }
+
+
+    #[test]
+    fn test_f1337patch_same_effect() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let mut other = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x00));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x00, 0x13));
+        other.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        other.add_patch(HexPatch::new(0x0000000000AF0200, 0x00, 0x37));
+        assert!(f1337patch.same_effect(&other));
+        assert!(other.same_effect(&f1337patch));
+
+        other.add_patch(HexPatch::new(0x0000000000AF0210, 0x00, 0x90));
+        assert!(!f1337patch.same_effect(&other));
+    }
+
+
+    #[test]
+    fn test_f1337patch_from_bufreader_with_spans() {
+        let content = concat!(
+            ">test.exe\r\n",
+            "# Skip the license check.\r\n",
+            "0000000000AF0200:13->37\r\n",
+            ";\r\n",
+            "0000000000AF0206:37->37\r\n",
+        );
+        let (f1337patch, spans) = F1337Patch::from_bufreader_with_spans(&mut io::Cursor::new(content)).unwrap();
+
+        assert_eq!(f1337patch.patches.len(), 2);
+        assert_eq!(spans, vec![(3, 38), (5, 66)]);
+        for (patch, (_, offset)) in f1337patch.patches.iter().zip(&spans) {
+            assert!(content[*offset..].starts_with(&patch.to_string()));
+        }
+    }
+
+
+    #[test]
+    fn test_f1337patch_validate_new_allowlist() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::nop(0x0000000000AF0200, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0210, 0x74, 0xEB));
+
+        assert_eq!(f1337patch.validate_new_allowlist(&[X86_NOP]), vec![1, 2]);
+        assert_eq!(f1337patch.validate_new_allowlist(&[X86_NOP, 0xEB]), vec![1]);
+        assert_eq!(f1337patch.validate_new_allowlist(&[]), vec![0, 1, 2]);
+    }
+
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_f1337patch_random_fixture() {
+        let f1337patch = F1337Patch::random_fixture("test.exe", 64, 1337);
+
+        assert_eq!(f1337patch.target_filename, "test.exe");
+        assert_eq!(f1337patch.patches.len(), 64);
+        assert_eq!(f1337patch, F1337Patch::random_fixture("test.exe", 64, 1337));
+        assert_ne!(f1337patch, F1337Patch::random_fixture("test.exe", 64, 7331));
+        assert!(f1337patch.self_check());
+    }
+
+
+    #[test]
+    fn test_f1337patch_best_match() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x01, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x00, X86_NOP));
+
+        let mut candidates = [
+            ("v1/test.exe", io::Cursor::new(vec![0x13, 0x00, 0x01])),
+            ("v2/test.exe", io::Cursor::new(vec![0x13, 0x37, 0x00])),
+            ("v3/test.exe", io::Cursor::new(vec![0x13, 0x37])),
         ];
+        assert_eq!(f1337patch.best_match(&mut candidates), Some("v2/test.exe"));
 
-        assert_eq!(dummy_patches, f1337path.patches);
+        let mut candidates = [("other.exe", io::Cursor::new(vec![0xFF; 3]))];
+        assert_eq!(f1337patch.best_match(&mut candidates), None);
+        assert_eq!(f1337patch.best_match::<io::Cursor<Vec<u8>>>(&mut []), None);
+    }
 
-        drop(dummy_file);
+
+    #[test]
+    fn test_f1337patch_to_string_opts() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert_eq!(f1337patch.to_string_opts(false), ">test.exe");
+        assert_eq!(f1337patch.to_string_opts(true), ">test.exe\n");
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x37));
+        assert_eq!(f1337patch.to_string_opts(true), ">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37\n");
+        assert_eq!(f1337patch.to_string_opts(false), ">test.exe\n0000000000AF0200:13->37\n0000000000AF0206:37->37");
+        assert_eq!(f1337patch.to_string(), f1337patch.to_string_opts(true));
+
+        let parsed = F1337Patch::from_str_content(&f1337patch.to_string_opts(false)).unwrap();
+        assert_eq!(parsed, f1337patch);
     }
 
+
     #[test]
-    fn test_f1337patch_from_bufreader() {
-        let mut dummy_file = tempfile().unwrap();
-        let f1337path: F1337Patch;
-        let dummy_patches: Vec<HexPatch>;
-        
-        writeln!(dummy_file, ">test.exe").unwrap();
-        writeln!(dummy_file, "0000000000AF0200:13->37").unwrap();
-        writeln!(dummy_file, "0000000000AF0206:37->37").unwrap();
+    fn test_f1337patch_apply_to_maybe() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let mut target = io::Cursor::new(vec![0x13, 0x00, 0x37]);
 
-        f1337path = F1337Patch::from_bufreader(&mut io::BufReader::new(&dummy_file)).unwrap();
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x37, 0x13));
 
-        assert_eq!(f1337path.target_filename, "test.exe");
-        assert_eq!(f1337path.patches.len(), 2);
+        assert_eq!(f1337patch.apply_to_maybe(&mut target, true).unwrap(), 2);
+        assert_eq!(target.get_ref(), &vec![0x13, 0x00, 0x37]);
 
-        dummy_patches = vec![
+        f1337patch.add_patch(HexPatch::new(0x01, 0x01, X86_NOP));
+        let old_value_mismatch = f1337patch.apply_to_maybe(&mut target, true).unwrap_err();
+        assert_eq!(old_value_mismatch, PatchFileError::OldValueMismatch { address: 0x01, expected: 0x01, found: 0x00 });
+
+        f1337patch.patches.pop();
+        assert_eq!(f1337patch.apply_to_maybe(&mut target, false).unwrap(), 2);
+        assert_eq!(target.into_inner(), vec![0x37, 0x00, 0x13]);
+    }
+
+
+    #[test]
+    fn test_f1337patchset_find_cross_file_address_clashes() {
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut game = F1337Patch::new("game.exe".to_string());
+        let mut engine = F1337Patch::new("engine.dll".to_string());
+        let mut game_again = F1337Patch::new("game.exe".to_string());
+
+        game.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        game.add_patch(HexPatch::new(0x0000000000AF0206, 0x37, 0x13));
+        engine.add_patch(HexPatch::new(0x0000000000AF0206, 0x00, 0x90));
+        engine.add_patch(HexPatch::new(0x0000000000AF0206, 0x00, 0x90));
+        engine.add_patch(HexPatch::new(0x0000000000AF0210, 0x00, 0x90));
+        game_again.add_patch(HexPatch::new(0x0000000000AF0200, 0x13, 0x37));
+        f1337patchset.add_file(game);
+        f1337patchset.add_file(engine);
+        f1337patchset.add_file(game_again);
+
+        assert_eq!(f1337patchset.find_cross_file_address_clashes(), vec![
+            ("game.exe".to_string(), "engine.dll".to_string(), 0x0000000000AF0206),
+        ]);
+    }
+
+
+    #[test]
+    fn test_f1337patch_run_lengths() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+
+        assert!(f1337patch.run_lengths().is_empty());
+
+        f1337patch.add_patch(HexPatch::new(0x0000000000000102, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000100, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000101, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x0000000000000101, 0x00, 0x90));
+        assert_eq!(f1337patch.run_lengths(), vec![3, 1]);
+
+        f1337patch.sort_by_address();
+        f1337patch.dedup_all();
+        let runs: Vec<usize> = f1337patch.runs().iter().map(|run| run.len()).collect();
+        assert_eq!(runs, f1337patch.run_lengths());
+    }
+
+    #[test]
+    fn test_to_debugger_script() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        f1337patch.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0xAF0206, 0x74, 0x0B));
+
+        assert_eq!(f1337patch.to_debugger_script(DebuggerFormat::X64dbg), "mov byte:[0xAF0200], 0x37\nmov byte:[0xAF0206], 0x0B\n");
+        assert_eq!(f1337patch.to_debugger_script(DebuggerFormat::WinDbg), "eb 0xAF0200 0x37\neb 0xAF0206 0x0B\n");
+        assert_eq!(f1337patch.to_debugger_script(DebuggerFormat::Gdb), "set {char}0xAF0200 = 0x37\nset {char}0xAF0206 = 0x0B\n");
+        assert_eq!(F1337Patch::new("test.exe".to_string()).to_debugger_script(DebuggerFormat::Gdb), "");
+    }
+
+    #[test]
+    fn test_is_strictly_valid() {
+        assert!(F1337Patch::is_strictly_valid(">test.exe\n0000000000AF0200:13->37\r\n0000000000AF0206:74->EB\n"));
+
+        // The lenient parser accepts the trailing space, the strict one rejects it.
+        let trailing_space = ">test.exe\n0000000000AF0200:13->37 \n";
+        let options = ParseOptions { lenient: true, ..ParseOptions::default() };
+        assert!(F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(trailing_space), &options).is_ok());
+        assert!(!F1337Patch::is_strictly_valid(trailing_space));
+
+        // Content rejected by both parsers is not valid either.
+        assert!(!F1337Patch::is_strictly_valid(">test.exe\n0000000000AF0200:13->3\n"));
+    }
+
+    #[test]
+    fn test_iter_sorted() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        f1337patch.add_patch(HexPatch::new(0xAF0206, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0xAF0206, 0xEB, 0x90));
+        f1337patch.add_patch(HexPatch::new(0xAF0100, 0x00, 0x01));
+        let original = f1337patch.patches.clone();
+
+        assert_eq!(f1337patch.iter_sorted().cloned().collect::<Vec<_>>(), vec![
+            HexPatch::new(0xAF0100, 0x00, 0x01),
             HexPatch::new(0xAF0200, 0x13, 0x37),
-            HexPatch::new(0xAF0206, 0x37, 0x37),
-        ];
+            HexPatch::new(0xAF0206, 0x74, 0xEB),
+            HexPatch::new(0xAF0206, 0xEB, 0x90),
+        ]);
+        assert_eq!(f1337patch.patches, original);
+    }
 
-        assert_eq!(dummy_patches, f1337path.patches);
+    #[test]
+    fn test_required_file_size() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        assert_eq!(f1337patch.required_file_size(), 0);
 
-        drop(dummy_file);
+        f1337patch.add_patch(HexPatch::new(0x1FF, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x000, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x100, 0x37, 0x13));
+
+        assert_eq!(f1337patch.required_file_size(), 0x200);
+        assert!(f1337patch.fits_in(f1337patch.required_file_size()));
+        assert!(!f1337patch.fits_in(f1337patch.required_file_size() - 1));
     }
 
     #[test]
-    fn test_check_patch_line_format_wrong_format() {
-        let lines = vec![
-            "0000000000AF0200:13->3",
-            "000000AF0200:13->32",
-            "0000000000AF020089:13->3A",
-            "0000000000AF0200:13->ZA",
-            "0000000000AF02KK:13->3A",
-        ];
+    fn test_f1337patchset_apply_to_dir_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut f1337patchset = F1337PatchSet::new();
+        let mut game = F1337Patch::new("game.exe".to_string());
+        let mut engine = F1337Patch::new("engine.dll".to_string());
+        let mut missing = F1337Patch::new("missing.dll".to_string());
 
-        for line in lines {
-            let wrong_format = F1337Patch::check_patch_line_format(&line.to_string()).unwrap_err();
-            assert_eq!(wrong_format, PatchFileError::WrongFormat);
-        };
+        std::fs::write(dir.path().join("game.exe"), [0x13, 0x37, 0x00]).unwrap();
+        std::fs::write(dir.path().join("engine.dll"), [0x00, 0x13]).unwrap();
+        game.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        game.add_patch(HexPatch::new(0x02, 0x00, 0x90));
+        engine.add_patch(HexPatch::new(0x01, 0x13, 0x37));
+        missing.add_patch(HexPatch::new(0x00, 0x00, 0x90));
+        f1337patchset.add_file(game);
+        f1337patchset.add_file(engine);
+        f1337patchset.add_file(missing);
+
+        // The last section mismatches: nothing is written, not even to the first file.
+        let mut mismatching = F1337Patch::new("engine.dll".to_string());
+        mismatching.add_patch(HexPatch::new(0x00, 0xFF, 0x90));
+        f1337patchset.add_file(mismatching);
+
+        assert_eq!(f1337patchset.apply_to_dir_atomic(dir.path()).unwrap_err(), PatchFileError::OldValueMismatch { address: 0x00, expected: 0xFF, found: 0x00 });
+        assert_eq!(std::fs::read(dir.path().join("game.exe")).unwrap(), vec![0x13, 0x37, 0x00]);
+        assert_eq!(std::fs::read(dir.path().join("engine.dll")).unwrap(), vec![0x00, 0x13]);
+
+        // A section verified against the result of a previous section of the same file.
+        f1337patchset.files.pop();
+        let mut chained = F1337Patch::new("engine.dll".to_string());
+        chained.add_patch(HexPatch::new(0x01, 0x37, 0x90));
+        f1337patchset.add_file(chained);
+
+        let changed = f1337patchset.apply_to_dir_atomic(dir.path()).unwrap();
+        assert_eq!(changed, HashMap::from([("game.exe".to_string(), 2), ("engine.dll".to_string(), 2)]));
+        assert!(!changed.contains_key("missing.dll"));
+        assert_eq!(std::fs::read(dir.path().join("game.exe")).unwrap(), vec![0x37, 0x37, 0x90]);
+        assert_eq!(std::fs::read(dir.path().join("engine.dll")).unwrap(), vec![0x00, 0x90]);
+        assert!(!dir.path().join("missing.dll").exists());
     }
 
     #[test]
-    fn test_get_filename_wrong_format() {
-        let wrong_format = F1337Patch::get_filename("test.exe".to_string()).unwrap_err();
+    fn test_suspicious_gaps() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        f1337patch.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0xAF0206, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0xAF0200, 0x37, 0x13));
+        f1337patch.add_patch(HexPatch::new(0xAF1000, 0x00, 0x90));
+
+        assert_eq!(f1337patch.suspicious_gaps(0x1000), Vec::<usize>::new());
+        assert_eq!(f1337patch.suspicious_gaps(0x100), vec![3]);
+
+        // An address which gained a digit is flagged itself.
+        f1337patch.add_patch(HexPatch::new(0xAF02060, 0x74, 0xEB));
+        assert_eq!(f1337patch.suspicious_gaps(0x1000), vec![4]);
+
+        // An address which lost digits is flagged itself too.
+        f1337patch.patches.pop();
+        f1337patch.add_patch(HexPatch::new(0x2, 0x74, 0xEB));
+        assert_eq!(f1337patch.suspicious_gaps(0x1000), vec![4]);
+
+        // Each gap flags one patch, in address order.
+        f1337patch.add_patch(HexPatch::new(0xAF02060, 0x74, 0xEB));
+        assert_eq!(f1337patch.suspicious_gaps(0x1000), vec![4, 5]);
+        assert_eq!(f1337patch.suspicious_gaps(0x100), vec![4, 3, 5]);
+        assert_eq!(F1337Patch::new("test.exe".to_string()).suspicious_gaps(0), Vec::<usize>::new());
+
+        // Between two groups of addresses, the patch after the gap is flagged.
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        for address in [0x100, 0x101, 0x9000, 0x9001] {
+            f1337patch.add_patch(HexPatch::new(address, 0x00, 0x90));
+        }
+        assert_eq!(f1337patch.suspicious_gaps(0x10), vec![2]);
+
+        // An outlier between them is flagged once for both of its gaps.
+        f1337patch.add_patch(HexPatch::new(0x5000, 0x00, 0x90));
+        assert_eq!(f1337patch.suspicious_gaps(0x10), vec![4]);
+    }
+
+    #[test]
+    fn test_to_rust_code() {
+        let mut f1337patch = F1337Patch::new("dir\\\"test\".exe".to_string());
+        f1337patch.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0xAF0206, 0x74, 0x0B));
+
+        let code = f1337patch.to_rust_code("baked");
+
+        assert!(code.starts_with("let mut baked = F1337Patch::new(\"dir\\\\\\\"test\\\".exe\".to_string());\n"));
+        assert!(code.contains("baked.add_patch(HexPatch::new(0xAF0200, 0x13, 0x37));\n"));
+        assert!(code.contains("baked.add_patch(HexPatch::new(0xAF0206, 0x74, 0x0B));\n"));
+        assert_eq!(code.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_strip_bom() {
+        let options = ParseOptions { strip_bom: true, ..ParseOptions::default() };
+        let content = "\u{FEFF}>test.exe\n0000000000AF0200:13->37\n\u{FEFF}0000000000AF0206:74->EB\n\u{FEFF}; comment\n";
+
+        let f1337patch = F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(content), &options).unwrap();
+
+        assert_eq!(f1337patch.target_filename, "test.exe");
+        assert_eq!(f1337patch.patches, vec![
+            HexPatch::new(0xAF0200, 0x13, 0x37),
+            HexPatch::new(0xAF0206, 0x74, 0xEB),
+        ]);
+
+        // Only a single leading BOM is stripped.
+        let doubled = ">test.exe\n\u{FEFF}\u{FEFF}0000000000AF0200:13->37\n";
+        assert!(F1337Patch::from_bufreader_with_options(&mut io::Cursor::new(doubled), &options).is_err());
+
+        // And BOMs are rejected by default.
+        assert!(F1337Patch::from_str_content(">test.exe\n\u{FEFF}0000000000AF0206:74->EB\n").is_err());
+        assert!(F1337Patch::from_str_content("\u{FEFF}>test.exe\n").is_err());
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_version_marker() {
+        let f1337patch = F1337Patch::from_str_content(">test.exe\n@v1\r\n0000000000AF0200:13->37\n").unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0xAF0200, 0x13, 0x37)]);
+
+        // An unknown version is read with version 1 semantics, with a warning.
+        let content = ">test.exe\n@v99\n0000000000AF0200:13->37\n";
+        let f1337patch = F1337Patch::from_str_content(content).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches.len(), 1);
 
+        let (f1337patch, warnings) = F1337Patch::from_bufreader_with_warnings(&mut io::Cursor::new(content)).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(warnings, vec![ParseWarning::UnknownVersion(99)]);
+
+        let (_, warnings) = F1337Patch::from_bufreader_with_warnings(&mut io::Cursor::new(">test.exe\n@v1\n")).unwrap();
+        assert_eq!(warnings, vec![]);
+
+        // The marker must be right after the header, and a version number.
+        assert_eq!(F1337Patch::from_str_content(">test.exe\n0000000000AF0200:13->37\n@v1\n").unwrap_err(), PatchFileError::WrongFormat);
+        assert_eq!(F1337Patch::from_str_content(">test.exe\n@v+2\n").unwrap_err(), PatchFileError::WrongFormat);
+        assert_eq!(F1337Patch::from_str_content(">test.exe\n@v\n").unwrap_err(), PatchFileError::WrongFormat);
+
+        let (f1337patch, spans) = F1337Patch::from_bufreader_with_spans(&mut io::Cursor::new(">test.exe\n@v1\n0000000000AF0200:13->37\n")).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(spans, vec![(3, 14)]);
+    }
+
+    #[test]
+    fn test_f1337patch_delta() {
+        let mut f1337patch = F1337Patch::new("test.exe".to_string());
+        let mut newer = F1337Patch::new("test.exe".to_string());
+        let original = vec![0x13, 0x74, 0x00, 0x05, 0x20];
+
+        f1337patch.add_patch(HexPatch::new(0x00, 0x13, 0x37));
+        f1337patch.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x00, 0x90));
+        f1337patch.add_patch(HexPatch::new(0x02, 0x00, 0x91));
+        newer.add_patch(HexPatch::new(0x00, 0x13, 0x38));
+        newer.add_patch(HexPatch::new(0x01, 0x74, 0xEB));
+        newer.add_patch(HexPatch::new(0x03, 0x05, 0x06));
+        newer.add_patch(HexPatch::new(0x04, 0x20, 0x20));
+        newer.format_version = 2;
+
+        let delta = f1337patch.delta(&newer);
+        assert_eq!(delta.patches, vec![
+            HexPatch::new(0x00, 0x37, 0x38),
+            HexPatch::new(0x02, 0x91, 0x00),
+            HexPatch::new(0x03, 0x05, 0x06),
+        ]);
+        assert_eq!(delta.format_version, 2);
+
+        // Applying the delta over the old version gives the same bytes as applying the newer version.
+        let patched = f1337patch.apply_to_cursor(original.clone()).unwrap();
+        assert_eq!(delta.apply_to_cursor(patched).unwrap(), newer.apply_to_cursor(original).unwrap());
+        assert!(f1337patch.delta(&f1337patch).patches.is_empty());
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_find_header_version_marker() {
+        let mut content = "Exported by x64dbg\n>test.exe\n@v1\n0000000000AF0200:13->37\n".as_bytes();
+
+        let f1337patch = F1337Patch::from_bufreader_find_header(&mut content, 4).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0xAF0200, 0x13, 0x37)]);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_os_version_marker() {
+        let mut content = io::Cursor::new(b">caf\xE9.exe\r\n@v1\r\n0000000000AF0200:13->37\r\n".to_vec());
+
+        let (f1337patch, filename) = F1337Patch::from_bufreader_os(&mut content).unwrap();
+        assert_eq!(filename, b"caf\xE9.exe");
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0xAF0200, 0x13, 0x37)]);
+    }
+
+    #[test]
+    fn test_f1337patch_from_bufreader_lossy_utf8_version_marker() {
+        let mut content = io::Cursor::new(b">test.exe\n@v1\n# Caf\xE9\n0000000000AF0200:13->37\n".to_vec());
+
+        let f1337patch = F1337Patch::from_bufreader_lossy_utf8(&mut content).unwrap();
+        assert_eq!(f1337patch.format_version(), 1);
+        assert_eq!(f1337patch.patches, vec![HexPatch::new(0xAF0200, 0x13, 0x37)]);
+
+        // The marker is only allowed right after the header.
+        let mut content = io::Cursor::new(b">test.exe\n0000000000AF0200:13->37\n@v1\n".to_vec());
+        assert_eq!(F1337Patch::from_bufreader_lossy_utf8(&mut content).unwrap_err(), PatchFileError::WrongFormat);
+    }
+
+    #[test]
+    fn test_f1337patch_stream_apply_version_marker() {
+        let mut patch_reader = ">test.exe\n@v1\n0000000000000001:13->37\n".as_bytes();
+        let mut target = io::Cursor::new(vec![0x00, 0x13]);
+
+        assert_eq!(F1337Patch::stream_apply(&mut patch_reader, &mut target).unwrap(), 1);
+        assert_eq!(target.into_inner(), vec![0x00, 0x37]);
+
+        let mut patch_reader = ">test.exe\n@vx\n0000000000000001:13->37\n".as_bytes();
+        let wrong_format = F1337Patch::stream_apply(&mut patch_reader, &mut io::Cursor::new(vec![0x00, 0x13])).unwrap_err();
         assert_eq!(wrong_format, PatchFileError::WrongFormat);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_f1337patch_stream_apply_stats_version_marker() {
+        let mut patch_reader = ">test.exe\n@v99\n0000000000000001:13->37\n0000000000000000:37->37\n".as_bytes();
+
+        let stats = F1337Patch::stream_apply_stats(&mut patch_reader, &mut io::Cursor::new(vec![0x00, 0x13])).unwrap();
+        assert_eq!(stats, StreamStats { applied: 1, skipped_noop: 1, mismatched: 0 });
+    }
+}